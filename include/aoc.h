@@ -0,0 +1,38 @@
+/* Generated from src/ffi.rs, do not edit by hand. */
+#ifndef AOC_H
+#define AOC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define AOC_OK 0 /* success */
+#define AOC_INVALID_ARGUMENT 1 /* invalid day, part or pointer */
+#define AOC_INVALID_INPUT 2 /* input is not valid UTF-8 */
+#define AOC_SOLVE_FAILED 3 /* input could not be solved */
+#define AOC_BUFFER_TOO_SMALL 4 /* output buffer is too small */
+#define AOC_PANIC 5 /* solver panicked */
+
+/*
+ * Solves part `part` (1 or 2) of day `day` (1 to 25) for the `len` bytes of
+ * UTF-8 text at `input`. Both parts are solved on every call.
+ *
+ * `*out_len` must hold the capacity of `out_buf` in bytes. On success the answer
+ * is written to `out_buf` as a NUL-terminated string. On success and on
+ * AOC_BUFFER_TOO_SMALL, `*out_len` is set to the length of the answer without
+ * the NUL terminator.
+ */
+int aoc_solve(uint32_t day, uint32_t part, const uint8_t *input, size_t len,
+              char *out_buf, size_t *out_len);
+
+/* Returns a static description of a status code returned by aoc_solve. */
+const char *aoc_status_message(int status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AOC_H */
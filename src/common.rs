@@ -10,6 +10,42 @@ pub use std::mem::swap;
 use std::str::FromStr;
 use std::sync::Mutex;
 
+mod arena;
+mod automaton;
+mod bit_grid;
+mod bits;
+mod counter;
+mod cycle;
+mod graph;
+mod grid;
+mod interval;
+mod matrix;
+mod memo;
+mod parallel;
+mod point;
+mod scan;
+mod search;
+mod union_find;
+mod visual;
+pub use crate::scan;
+pub use arena::*;
+pub use automaton::*;
+pub use bit_grid::*;
+pub use bits::*;
+pub use counter::*;
+pub use cycle::*;
+pub use graph::*;
+pub use grid::*;
+pub use interval::*;
+pub use matrix::*;
+pub use memo::*;
+pub use parallel::*;
+pub use point::*;
+pub use scan::*;
+pub use search::*;
+pub use union_find::*;
+pub use visual::*;
+
 pub type HashMap<K, V> = std::collections::HashMap<K, V, fnv::FnvBuildHasher>;
 pub type Result<T = (), E = Error> = std::result::Result<T, E>;
 pub type Lines<'a> = &'a [&'a str];
@@ -104,3 +140,116 @@ pub fn find_regex<'t>(pattern: &str, string: &'t str) -> Option<regex::Captures<
 pub fn find_regex_all<'t>(pattern: &str, string: &'t str) -> regex::CaptureMatches<'static, 't> {
     compile(pattern).captures_iter(string)
 }
+
+thread_local! {
+    static ANSWERS: std::cell::RefCell<Vec<(char, String)>> = Default::default();
+    static VERBOSE: std::cell::Cell<bool> = const { std::cell::Cell::new(true) };
+}
+
+/// Enables or disables printing of answers and progress on this thread.
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.with(|v| v.set(verbose));
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.with(|v| v.get())
+}
+
+/// Prints the answer to one part of a puzzle and records it so the runner can inspect it later.
+/// Answers spanning multiple lines, such as renderings, start on the line after the label.
+pub fn answer(part: char, value: impl Display) {
+    let value = value.to_string();
+
+    if is_verbose() {
+        if value.contains('\n') {
+            println!("part {}:\n{}", part, value);
+        } else {
+            println!("part {}: {}", part, value);
+        }
+    }
+
+    ANSWERS.with(|answers| answers.borrow_mut().push((part, value)));
+}
+
+/// Returns the answers recorded by [`answer`] on this thread since the previous call.
+pub fn take_answers() -> Vec<(char, String)> {
+    ANSWERS.with(|answers| answers.take())
+}
+
+#[cfg(test)]
+pub use rand::RngExt;
+
+#[cfg(test)]
+pub fn test_rng() -> rand::rngs::SmallRng {
+    rand::SeedableRng::seed_from_u64(2021)
+}
+
+/// Feeds `solver` with random bytes and randomly corrupted copies of `seed`, failing if it panics.
+/// Returning an error is fine, the point is that malformed input never brings down the runner.
+#[cfg(test)]
+pub fn fuzz<F>(seed: Lines, solver: F)
+where
+    F: Fn(Lines) -> Result,
+{
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn mutate(rng: &mut impl RngExt, seed: &[u8]) -> Vec<u8> {
+        let mut bytes = seed.to_vec();
+
+        for _ in 0..rng.random_range(1..=3) {
+            let i = rng.random_range(0..=bytes.len());
+
+            // Bytes taken from the seed keep the input close to the expected format.
+            let similar = seed.get(rng.random_range(0..=seed.len()));
+            let similar = similar.copied().unwrap_or(b'0');
+
+            match rng.random_range(0..7) {
+                0 if i < bytes.len() => bytes[i] = similar,
+                1 if i < bytes.len() => bytes[i] = rng.random(),
+                2 if i < bytes.len() => {
+                    bytes.remove(i);
+                }
+                3 => bytes.insert(i, similar),
+                4 => {
+                    bytes.splice(i..i, *b"9999999999");
+                }
+                5 => bytes.truncate(i),
+                _ => {
+                    let mut lines = bytes
+                        .split(|&b| b == b'\n')
+                        .map(<[u8]>::to_vec)
+                        .collect_vec();
+                    let k = rng.random_range(0..lines.len());
+
+                    if rng.random() {
+                        lines.insert(k, lines[k].clone());
+                    } else {
+                        lines.remove(k);
+                    }
+
+                    bytes = lines.join(&b'\n');
+                }
+            }
+        }
+
+        bytes
+    }
+
+    let mut rng = test_rng();
+    let seed = seed.join("\n").into_bytes();
+
+    for iter in 0..100 {
+        let bytes = if iter % 10 == 0 {
+            (0..rng.random_range(0..64)).map(|_| rng.random()).collect()
+        } else {
+            mutate(&mut rng, &seed)
+        };
+
+        let content = String::from_utf8_lossy(&bytes);
+        let lines = content.trim().split('\n').collect_vec();
+
+        if catch_unwind(AssertUnwindSafe(|| solver(&lines))).is_err() {
+            panic!("solver panicked on input: {:?}", content);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Arena that interns states into compact IDs, for searches over very many states.
+use std::hash::{Hash, Hasher};
+use std::ops::Index;
+
+pub type StateId = u32;
+
+const EMPTY: StateId = StateId::MAX;
+
+/// Stores every distinct state once and identifies it by a `u32` ID, in insertion order. Each
+/// state has an optional parent, which allows reconstructing the path to it. The lookup table is
+/// an open addressing hash table of IDs, so apart from the states themselves every entry costs
+/// only a few words.
+#[derive(Debug, Clone)]
+pub struct Arena<S> {
+    states: Vec<S>,
+    parents: Vec<StateId>,
+    slots: Vec<StateId>,
+}
+
+impl<S> Default for Arena<S> {
+    fn default() -> Self {
+        Self {
+            states: vec![],
+            parents: vec![],
+            slots: vec![],
+        }
+    }
+}
+
+fn hash_state<S: Hash>(state: &S) -> usize {
+    let mut hasher = fnv::FnvHasher::default();
+    state.hash(&mut hasher);
+    hasher.finish() as usize
+}
+
+impl<S: Hash + Eq> Arena<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct states.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Index of the slot that holds `state`, or of the empty slot where it would be stored.
+    fn slot(&self, state: &S) -> usize {
+        let mask = self.slots.len() - 1;
+        let mut index = hash_state(state) & mask;
+
+        loop {
+            let id = self.slots[index];
+            if id == EMPTY || self.states[id as usize] == *state {
+                return index;
+            }
+
+            index = (index + 1) & mask;
+        }
+    }
+
+    fn grow(&mut self) {
+        let len = (2 * self.slots.len()).max(16);
+        self.slots = vec![EMPTY; len];
+
+        for id in 0..self.states.len() {
+            let index = self.slot(&self.states[id]);
+            self.slots[index] = id as StateId;
+        }
+    }
+
+    pub fn find(&self, state: &S) -> Option<StateId> {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        match self.slots[self.slot(state)] {
+            EMPTY => None,
+            id => Some(id),
+        }
+    }
+
+    /// Returns the ID of `state` and whether it is new. The parent is only set for new states,
+    /// use [`Arena::set_parent`] to change it afterwards.
+    pub fn insert(&mut self, state: S, parent: Option<StateId>) -> (StateId, bool) {
+        // Keep the load factor below one half, so probe sequences stay short.
+        if 2 * (self.states.len() + 1) > self.slots.len() {
+            self.grow();
+        }
+
+        let index = self.slot(&state);
+        if self.slots[index] != EMPTY {
+            return (self.slots[index], false);
+        }
+
+        let id = self.states.len() as StateId;
+        assert!(id != EMPTY, "too many states");
+
+        self.slots[index] = id;
+        self.states.push(state);
+        self.parents.push(parent.unwrap_or(EMPTY));
+        (id, true)
+    }
+
+    pub fn get(&self, id: StateId) -> &S {
+        &self.states[id as usize]
+    }
+
+    pub fn parent(&self, id: StateId) -> Option<StateId> {
+        match self.parents[id as usize] {
+            EMPTY => None,
+            parent => Some(parent),
+        }
+    }
+
+    pub fn set_parent(&mut self, id: StateId, parent: Option<StateId>) {
+        self.parents[id as usize] = parent.unwrap_or(EMPTY);
+    }
+
+    /// IDs of the states from the root up to and including `id`, by following the parents.
+    pub fn path(&self, id: StateId) -> Vec<StateId> {
+        let mut path = vec![id];
+
+        while let Some(parent) = self.parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+impl<S> Index<StateId> for Arena<S> {
+    type Output = S;
+
+    fn index(&self, id: StateId) -> &S {
+        &self.states[id as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena() {
+        let mut arena = Arena::new();
+        assert_eq!(arena.find(&"a"), None);
+
+        let (a, new) = arena.insert("a", None);
+        assert!(new);
+        let (b, _) = arena.insert("b", Some(a));
+        let (c, _) = arena.insert("c", Some(b));
+        assert_eq!(arena.insert("b", None), (b, false));
+        assert_eq!(arena.parent(b), Some(a));
+        assert_eq!(arena.path(c), [a, b, c]);
+
+        arena.set_parent(c, Some(a));
+        assert_eq!(arena.path(c), [a, c]);
+        assert_eq!((arena[c], arena.find(&"c")), ("c", Some(c)));
+
+        // Growing the table keeps all IDs.
+        let mut arena = Arena::new();
+        for i in 0..1000u64 {
+            assert_eq!(arena.insert(i * i, None), (i as StateId, true));
+        }
+
+        assert_eq!(arena.len(), 1000);
+        assert_eq!(arena.find(&250000), Some(500));
+        assert_eq!(arena.find(&2), None);
+        assert_eq!(arena.get(999), &998001);
+    }
+}
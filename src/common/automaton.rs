@@ -0,0 +1,269 @@
+//! Cellular automata on a grid, with rules that look at the neighborhood of every cell.
+use super::grid::{OFFSETS4, OFFSETS8};
+use super::*;
+use std::ops::Index;
+
+/// What lies beyond the edges of the grid.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Boundary<T> {
+    /// There are no cells outside the grid.
+    Fixed,
+    /// The grid wraps around at its edges.
+    Toroidal,
+    /// The grid is surrounded by infinitely many cells with the same value. The grid grows by the
+    /// radius on every side in every step, and the background evolves by applying the rule to a
+    /// neighborhood that only contains background cells.
+    Uniform(T),
+}
+
+/// Grid of cells that all change at once, based on the cells within their neighborhood.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Automaton<T> {
+    grid: Grid<T>,
+    boundary: Boundary<T>,
+    radius: usize,
+}
+
+/// The cells around one cell of an automaton, addressed by their `[row, column]` offset.
+pub struct Neighborhood<'a, T> {
+    automaton: &'a Automaton<T>,
+    pos: [isize; 2],
+}
+
+impl<'a, T> Neighborhood<'a, T> {
+    /// The cell at offset `delta`, or `None` if it lies outside a fixed boundary.
+    pub fn get(&self, [di, dj]: [isize; 2]) -> Option<&'a T> {
+        self.automaton.cell([self.pos[0] + di, self.pos[1] + dj])
+    }
+
+    pub fn center(&self) -> &'a T {
+        self.get([0, 0]).unwrap()
+    }
+
+    /// The horizontal and vertical neighbors that exist.
+    pub fn neighbors4(&self) -> impl Iterator<Item = &'a T> + '_ {
+        OFFSETS4.iter().filter_map(move |&delta| self.get(delta))
+    }
+
+    /// The horizontal, vertical and diagonal neighbors that exist.
+    pub fn neighbors8(&self) -> impl Iterator<Item = &'a T> + '_ {
+        OFFSETS8.iter().filter_map(move |&delta| self.get(delta))
+    }
+}
+
+impl<T> Index<[isize; 2]> for Neighborhood<'_, T> {
+    type Output = T;
+
+    fn index(&self, delta: [isize; 2]) -> &T {
+        self.get(delta)
+            .unwrap_or_else(|| panic!("offset outside of the boundary: {:?}", delta))
+    }
+}
+
+impl<T> Automaton<T> {
+    /// Creates an automaton whose rules only look at cells at most one row and column away.
+    pub fn new(grid: Grid<T>, boundary: Boundary<T>) -> Self {
+        Self {
+            grid,
+            boundary,
+            radius: 1,
+        }
+    }
+
+    /// Sets how many rows and columns away from a cell the rule looks, which is how far an
+    /// automaton with a uniform boundary grows in every step.
+    pub fn radius(mut self, radius: usize) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    pub fn into_grid(self) -> Grid<T> {
+        self.grid
+    }
+
+    /// The value of all cells outside the grid, if the boundary is uniform.
+    pub fn background(&self) -> Option<&T> {
+        match &self.boundary {
+            Boundary::Uniform(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The cell at `[row, column]`, which may lie outside the grid.
+    pub fn cell(&self, [i, j]: [isize; 2]) -> Option<&T> {
+        let (n, m) = (self.grid.nrows() as isize, self.grid.ncols() as isize);
+
+        if (0..n).contains(&i) && (0..m).contains(&j) {
+            return Some(&self.grid[[i as usize, j as usize]]);
+        }
+
+        match &self.boundary {
+            Boundary::Fixed => None,
+            Boundary::Toroidal if n > 0 && m > 0 => {
+                Some(&self.grid[[i.rem_euclid(n) as usize, j.rem_euclid(m) as usize]])
+            }
+            Boundary::Toroidal => None,
+            Boundary::Uniform(value) => Some(value),
+        }
+    }
+}
+
+impl<T: Clone> Automaton<T> {
+    /// Updates all cells at once, every cell becomes the result of `rule` on its neighborhood.
+    pub fn step<F>(&mut self, mut rule: F)
+    where
+        F: FnMut(&Neighborhood<T>) -> T,
+    {
+        let margin = match self.boundary {
+            Boundary::Uniform(_) => self.radius,
+            _ => 0,
+        };
+
+        let (n, m) = (self.grid.nrows(), self.grid.ncols());
+        let shift = margin as isize;
+
+        let grid = Grid::from_fn(n + 2 * margin, m + 2 * margin, |[i, j]| {
+            rule(&Neighborhood {
+                automaton: self,
+                pos: [i as isize - shift, j as isize - shift],
+            })
+        });
+
+        if let Boundary::Uniform(_) = self.boundary {
+            // Far enough away from the grid that the neighborhood only contains background.
+            let far = -(2 * self.radius as isize) - 1;
+            let background = rule(&Neighborhood {
+                automaton: self,
+                pos: [far, far],
+            });
+
+            self.boundary = Boundary::Uniform(background);
+        }
+
+        self.grid = grid;
+    }
+
+    /// Performs one step that consists of `phases` updates, each of which sees the result of the
+    /// previous one. The rule is given the index of the current phase.
+    pub fn step_phases<F>(&mut self, phases: usize, mut rule: F)
+    where
+        F: FnMut(usize, &Neighborhood<T>) -> T,
+    {
+        for phase in 0..phases {
+            self.step(|cell| rule(phase, cell));
+        }
+    }
+
+    /// Repeats steps until no cell changes anymore, returns the number of steps that changed
+    /// something. With a uniform boundary the grid keeps growing, so this may never finish.
+    pub fn step_until_stable<F>(&mut self, mut rule: F) -> usize
+    where
+        T: PartialEq,
+        F: FnMut(&Neighborhood<T>) -> T,
+    {
+        let mut steps = 0;
+
+        loop {
+            let previous = self.clone();
+            self.step(&mut rule);
+
+            if *self == previous {
+                return steps;
+            }
+
+            steps += 1;
+        }
+    }
+
+    /// Removes the rows and columns along the edges of a uniform boundary that only contain
+    /// background cells. This does not change the automaton, but equal patterns then compare
+    /// equal regardless of how far the grid has grown.
+    pub fn trim(&mut self)
+    where
+        T: PartialEq,
+    {
+        let background = match &self.boundary {
+            Boundary::Uniform(value) => value,
+            _ => return,
+        };
+
+        let grid = &self.grid;
+        let rows = (0..grid.nrows())
+            .filter(|&i| grid.row(i).iter().any(|x| x != background))
+            .collect_vec();
+        let cols = (0..grid.ncols())
+            .filter(|&j| grid.column(j).any(|x| x != background))
+            .collect_vec();
+
+        self.grid = match (rows.first(), rows.last(), cols.first(), cols.last()) {
+            (Some(&i0), Some(&i1), Some(&j0), Some(&j1)) => {
+                Grid::from_fn(i1 - i0 + 1, j1 - j0 + 1, |[i, j]| {
+                    grid[[i0 + i, j0 + j]].clone()
+                })
+            }
+            _ => Grid::new(0, 0, background.clone()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_automaton() {
+        let parse = |lines: &[&str]| Grid::from_lines(lines, |c| Ok(c == '#')).unwrap();
+        let life = |cell: &Neighborhood<bool>| {
+            let alive = cell.neighbors8().filter(|&&b| b).count();
+            alive == 3 || (alive == 2 && *cell.center())
+        };
+
+        // A blinker oscillates, and keeps its size with a fixed boundary.
+        let blinker = parse(&["...", "###", "..."]);
+        let mut automaton = Automaton::new(blinker.clone(), Boundary::Fixed);
+        automaton.step(life);
+        assert_eq!(*automaton.grid(), parse(&[".#.", ".#.", ".#."]));
+        automaton.step(life);
+        assert_eq!(*automaton.grid(), blinker);
+
+        // A glider returns to its shape after four steps, one cell further along the diagonal.
+        let glider = parse(&[".#.", "..#", "###"]);
+        let mut automaton = Automaton::new(glider.clone(), Boundary::Uniform(false));
+        (0..4).for_each(|_| automaton.step(life));
+        assert_eq!(automaton.grid().nrows(), 11);
+        automaton.trim();
+        assert_eq!(*automaton.grid(), glider);
+
+        // On a torus, the glider also returns to its start.
+        let mut grid = Grid::new(5, 5, false);
+        for (pos, &b) in glider.indexed_iter() {
+            grid[pos] = b;
+        }
+
+        let mut automaton = Automaton::new(grid.clone(), Boundary::Toroidal);
+        (0..20).for_each(|_| automaton.step(life));
+        assert_eq!(*automaton.grid(), grid);
+
+        // Every phase sees the result of the previous one, so the cell moves twice.
+        let mut automaton = Automaton::new(parse(&["#...."]), Boundary::Fixed);
+        automaton.step_phases(2, |_, cell| cell.get([0, -1]) == Some(&true));
+        assert_eq!(*automaton.grid(), parse(&["..#.."]));
+
+        // A uniform background evolves with the rule as well.
+        let mut automaton = Automaton::new(parse(&["#"]), Boundary::Uniform(false));
+        automaton.step(|cell| !cell.center());
+        assert_eq!(automaton.background(), Some(&true));
+        assert_eq!(*automaton.grid(), parse(&["###", "#.#", "###"]));
+
+        // Spreading from one cell stabilizes once the grid is full.
+        let mut automaton = Automaton::new(parse(&["....", "#...", "...."]), Boundary::Fixed);
+        let steps =
+            automaton.step_until_stable(|cell| *cell.center() || cell.neighbors4().any(|&b| b));
+        assert_eq!(steps, 4);
+        assert!(automaton.grid().iter().all(|&b| b));
+    }
+}
@@ -0,0 +1,385 @@
+//! Two-dimensional grid of bits, packed into words so that whole rows are updated at once.
+use super::*;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+const WORD: usize = u64::BITS as usize;
+
+/// Grid of booleans where every row is stored as `u64` words, column `j` of a row is bit
+/// `j % 64` of word `j / 64`. Bits past the last column are always zero.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    nrows: usize,
+    ncols: usize,
+    words: Vec<u64>,
+}
+
+/// The 64 columns of `row` starting at column `start`, columns outside of the row are zero.
+fn read_word(row: &[u64], start: isize) -> u64 {
+    if start < 0 {
+        return match start.unsigned_abs() {
+            n if n < WORD => read_word(row, 0) << n,
+            _ => 0,
+        };
+    }
+
+    let (index, offset) = (start as usize / WORD, start as usize % WORD);
+    let low = row.get(index).map_or(0, |w| w >> offset);
+    let high = match row.get(index + 1) {
+        Some(w) if offset > 0 => w << (WORD - offset),
+        _ => 0,
+    };
+
+    low | high
+}
+
+/// Mask of the lowest `n` bits, for `n` up to 64.
+fn low_bits(n: usize) -> u64 {
+    if n >= WORD {
+        !0
+    } else {
+        (1 << n) - 1
+    }
+}
+
+impl BitGrid {
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            words: vec![0; nrows * ncols.div_ceil(WORD)],
+        }
+    }
+
+    pub fn from_fn<F>(nrows: usize, ncols: usize, mut fun: F) -> Self
+    where
+        F: FnMut(Pos) -> bool,
+    {
+        let mut output = Self::new(nrows, ncols);
+
+        for i in 0..nrows {
+            for j in 0..ncols {
+                output.set([i, j], fun([i, j]));
+            }
+        }
+
+        output
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    fn words_per_row(&self) -> usize {
+        self.ncols.div_ceil(WORD)
+    }
+
+    /// The words of row `i`.
+    pub fn row(&self, i: usize) -> &[u64] {
+        let n = self.words_per_row();
+        &self.words[i * n..(i + 1) * n]
+    }
+
+    /// Builds a grid of the same size, where every row is computed from the previous words.
+    fn map_rows<F>(&self, mut fun: F) -> Self
+    where
+        F: FnMut(usize, &mut [u64]),
+    {
+        let mut output = self.clone();
+        let n = self.words_per_row().max(1);
+
+        for (i, row) in output.words.chunks_mut(n).enumerate() {
+            fun(i, row);
+        }
+
+        output.clear_padding();
+        output
+    }
+
+    fn clear_padding(&mut self) {
+        let n = self.words_per_row();
+        let mask = low_bits(self.ncols - (n.max(1) - 1) * WORD);
+
+        if n > 0 {
+            for row in self.words.chunks_mut(n) {
+                row[n - 1] &= mask;
+            }
+        }
+    }
+
+    pub fn contains(&self, [i, j]: Pos) -> bool {
+        i < self.nrows && j < self.ncols
+    }
+
+    pub fn get(&self, [i, j]: Pos) -> bool {
+        assert!(self.contains([i, j]), "index out of bounds: {:?}", [i, j]);
+        (self.row(i)[j / WORD] >> (j % WORD)) & 1 == 1
+    }
+
+    pub fn set(&mut self, [i, j]: Pos, value: bool) {
+        assert!(self.contains([i, j]), "index out of bounds: {:?}", [i, j]);
+        let index = i * self.words_per_row() + j / WORD;
+        let word = &mut self.words[index];
+        let mask = 1 << (j % WORD);
+
+        if value {
+            *word |= mask;
+        } else {
+            *word &= !mask;
+        }
+    }
+
+    /// Number of bits that are set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Positions of the bits that are set, row by row.
+    pub fn ones(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.nrows)
+            .flat_map(move |i| (0..self.ncols).map(move |j| [i, j]))
+            .filter(move |&pos| self.get(pos))
+    }
+
+    /// The `width` bits of row `i` starting at column `j`, with column `j` as the most significant
+    /// bit. Cells outside the grid read as `outside`.
+    pub fn bits(&self, [i, j]: [isize; 2], width: usize, outside: bool) -> u64 {
+        assert!((1..=WORD).contains(&width), "invalid width: {}", width);
+
+        if !(0..self.nrows as isize).contains(&i) {
+            return if outside { low_bits(width) } else { 0 };
+        }
+
+        let mut word = read_word(self.row(i as usize), j) & low_bits(width);
+
+        if outside {
+            let start = (-j).clamp(0, width as isize) as usize;
+            let end = (self.ncols as isize - j).clamp(start as isize, width as isize) as usize;
+            let inside = low_bits(end) & !low_bits(start);
+            word |= low_bits(width) & !inside;
+        }
+
+        word.reverse_bits() >> (WORD - width)
+    }
+
+    /// Moves every bit `delta` columns to the right, or to the left if `delta` is negative. Bits
+    /// that are moved past an edge reappear on the other side if `wrap` is set.
+    pub fn shift_columns(&self, delta: isize, wrap: bool) -> Self {
+        let m = self.ncols as isize;
+
+        if wrap && m > 0 {
+            let delta = delta.rem_euclid(m);
+            return &self.shift_columns(delta, false) | &self.shift_columns(delta - m, false);
+        }
+
+        self.map_rows(|_, row| {
+            let input = row.to_vec();
+
+            for (k, word) in row.iter_mut().enumerate() {
+                *word = read_word(&input, (k * WORD) as isize - delta);
+            }
+        })
+    }
+
+    /// Moves every row `delta` rows down, or up if `delta` is negative. Rows that are moved past
+    /// an edge reappear on the other side if `wrap` is set.
+    pub fn shift_rows(&self, delta: isize, wrap: bool) -> Self {
+        let n = self.nrows as isize;
+
+        self.map_rows(|i, row| {
+            let source = if wrap && n > 0 {
+                Some((i as isize - delta).rem_euclid(n))
+            } else {
+                Some(i as isize - delta).filter(|i| (0..n).contains(i))
+            };
+
+            match source {
+                Some(source) => row.copy_from_slice(self.row(source as usize)),
+                None => row.fill(0),
+            }
+        })
+    }
+
+    /// Mirrors the grid vertically, the first row becomes the last.
+    pub fn flip_rows(&self) -> Self {
+        self.map_rows(|i, row| row.copy_from_slice(self.row(self.nrows - 1 - i)))
+    }
+
+    /// Mirrors the grid horizontally, the first column becomes the last.
+    pub fn flip_columns(&self) -> Self {
+        let padding = (self.words_per_row() * WORD - self.ncols) as isize;
+
+        self.map_rows(|_, row| {
+            let reversed = row.iter().rev().map(|w| w.reverse_bits()).collect_vec();
+
+            for (k, word) in row.iter_mut().enumerate() {
+                *word = read_word(&reversed, (k * WORD) as isize + padding);
+            }
+        })
+    }
+
+    /// The grid cut off or padded with zeros to the given size, keeping the top left corner.
+    pub fn resize(&self, nrows: usize, ncols: usize) -> Self {
+        let mut output = Self::new(nrows, ncols);
+        let n = output.words_per_row();
+
+        for i in 0..nrows.min(self.nrows) {
+            for k in 0..n {
+                output.words[i * n + k] = read_word(self.row(i), (k * WORD) as isize);
+            }
+        }
+
+        output.clear_padding();
+        output
+    }
+
+    /// Removes the rows and columns along the edges in which every bit equals `value`.
+    pub fn trim(&self, value: bool) -> Self {
+        let rows = (0..self.nrows)
+            .filter(|&i| (0..self.ncols).any(|j| self.get([i, j]) != value))
+            .collect_vec();
+        let cols = (0..self.ncols)
+            .filter(|&j| (0..self.nrows).any(|i| self.get([i, j]) != value))
+            .collect_vec();
+
+        match (rows.first(), rows.last(), cols.first(), cols.last()) {
+            (Some(&i0), Some(&i1), Some(&j0), Some(&j1)) => {
+                Self::from_fn(i1 - i0 + 1, j1 - j0 + 1, |[i, j]| {
+                    self.get([i0 + i, j0 + j])
+                })
+            }
+            _ => Self::new(0, 0),
+        }
+    }
+
+    fn zip_words<F>(&self, that: &Self, fun: F) -> Self
+    where
+        F: Fn(u64, u64) -> u64,
+    {
+        assert_eq!(
+            (self.nrows, self.ncols),
+            (that.nrows, that.ncols),
+            "grids have different sizes"
+        );
+
+        Self {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            words: self
+                .words
+                .iter()
+                .zip(&that.words)
+                .map(|(&a, &b)| fun(a, b))
+                .collect(),
+        }
+    }
+}
+
+impl BitAnd for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitand(self, that: Self) -> BitGrid {
+        self.zip_words(that, |a, b| a & b)
+    }
+}
+
+impl BitOr for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitor(self, that: Self) -> BitGrid {
+        self.zip_words(that, |a, b| a | b)
+    }
+}
+
+impl BitXor for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitxor(self, that: Self) -> BitGrid {
+        self.zip_words(that, |a, b| a ^ b)
+    }
+}
+
+impl Not for &BitGrid {
+    type Output = BitGrid;
+
+    fn not(self) -> BitGrid {
+        self.map_rows(|_, row| row.iter_mut().for_each(|w| *w = !*w))
+    }
+}
+
+/// Prints every row on its own line, with `#` for set bits and `.` otherwise.
+impl Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0..self.nrows {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            for j in 0..self.ncols {
+                write!(f, "{}", if self.get([i, j]) { '#' } else { '.' })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_grid() {
+        let parse = |lines: &[&str]| {
+            let grid = Grid::from_lines(lines, |c| Ok(c == '#')).unwrap();
+            BitGrid::from_fn(grid.nrows(), grid.ncols(), |pos| grid[pos])
+        };
+
+        let grid = parse(&["#..#.", ".##..", "....#"]);
+        assert_eq!(grid.to_string(), "#..#.\n.##..\n....#");
+        assert_eq!(grid.count_ones(), 5);
+        assert_eq!(grid.ones().take(3).collect_vec(), [[0, 0], [0, 3], [1, 1]]);
+
+        assert_eq!(grid.bits([1, 0], 4, false), 0b0110);
+        assert_eq!(grid.bits([0, -1], 3, true), 0b110);
+        assert_eq!(grid.bits([2, 3], 3, true), 0b011);
+        assert_eq!(grid.bits([-1, 0], 2, true), 0b11);
+
+        assert_eq!(
+            grid.shift_columns(1, false),
+            parse(&[".#..#", "..##.", "....."])
+        );
+        assert_eq!(
+            grid.shift_columns(1, true),
+            parse(&[".#..#", "..##.", "#...."])
+        );
+        assert_eq!(
+            grid.shift_rows(-1, true),
+            parse(&[".##..", "....#", "#..#."])
+        );
+        assert_eq!(
+            grid.flip_rows().flip_columns(),
+            parse(&["#....", "..##.", ".#..#"])
+        );
+        assert_eq!(grid.resize(2, 3), parse(&["#..", ".##"]));
+        assert_eq!(grid.resize(2, 3).trim(false), parse(&["#..", ".##"]));
+        assert_eq!((!&grid).count_ones(), 10);
+        assert_eq!((&grid & &grid.shift_rows(1, false)).count_ones(), 0);
+        assert_eq!((&grid ^ &grid), BitGrid::new(3, 5));
+
+        // Rows that span several words.
+        let wide = BitGrid::from_fn(2, 150, |[i, j]| (i + j) % 7 == 0);
+        let shifted = wide.shift_columns(-70, true).shift_columns(70, true);
+        assert_eq!(shifted, wide);
+        assert_eq!(wide.flip_columns().flip_columns(), wide);
+        assert_eq!(wide.flip_columns().get([0, 149 - 63]), wide.get([0, 63]));
+        assert_eq!(wide.count_ones(), (!&!&wide).count_ones());
+        assert_eq!(wide.bits([1, 62], 8, false), 0b1000_0001);
+        assert_eq!(wide.resize(2, 80).count_ones(), 12 + 11);
+        assert_eq!(wide.trim(false).ncols(), 148);
+    }
+}
@@ -0,0 +1,240 @@
+//! Reading and writing numbers of arbitrary bit width, most significant bit first.
+use super::*;
+
+/// Reads bits from a byte buffer, which can be decoded from hexadecimal or binary text.
+#[derive(Debug, Clone)]
+pub struct BitReader {
+    bytes: Vec<u8>,
+    len: usize,
+    position: usize,
+}
+
+impl BitReader {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            len: 8 * bytes.len(),
+            position: 0,
+        }
+    }
+
+    /// Decodes hexadecimal digits, each digit provides 4 bits.
+    pub fn from_hex(text: &str) -> Result<Self> {
+        let mut writer = BitWriter::new();
+
+        for c in text.chars() {
+            let digit = c
+                .to_digit(16)
+                .with_context(|| format!("invalid hex digit: {:?}", c))?;
+            writer.write_bits(digit as u64, 4);
+        }
+
+        Ok(writer.into_reader())
+    }
+
+    /// Decodes a string of `0` and `1` characters.
+    pub fn from_binary(text: &str) -> Result<Self> {
+        let mut writer = BitWriter::new();
+
+        for c in text.chars() {
+            match c {
+                '0' => writer.write_bit(false),
+                '1' => writer.write_bit(true),
+                _ => bail!("invalid binary digit: {:?}", c),
+            }
+        }
+
+        Ok(writer.into_reader())
+    }
+
+    /// Total number of bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of bits left to read.
+    pub fn remaining(&self) -> usize {
+        self.len - self.position
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool> {
+        ensure!(self.position < self.len, "unexpected end of input");
+
+        let byte = self.bytes[self.position / 8];
+        let bit = (byte >> (7 - self.position % 8)) & 1 == 1;
+        self.position += 1;
+
+        Ok(bit)
+    }
+
+    /// Reads an unsigned number of `n` bits, at most 64.
+    pub fn read_bits(&mut self, n: usize) -> Result<u64> {
+        ensure!(n <= 64, "cannot read {} bits at once", n);
+        ensure!(
+            n <= self.remaining(),
+            "unexpected end of input: {} bits requested, but only {} remaining",
+            n,
+            self.remaining()
+        );
+
+        let mut output = 0;
+        for _ in 0..n {
+            output = (output << 1) | self.read_bit()? as u64;
+        }
+
+        Ok(output)
+    }
+
+    /// Skips bits until the position is a multiple of `bits`, or until the end of the input.
+    pub fn align(&mut self, bits: usize) {
+        assert!(bits > 0, "cannot align to 0 bits");
+        let position = self.position.next_multiple_of(bits);
+        self.position = position.min(self.len);
+    }
+}
+
+/// Writes bits into a byte buffer, the unused bits of the last byte are zero.
+#[derive(Debug, Clone, Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of bits written so far.
+    pub fn position(&self) -> usize {
+        self.len
+    }
+
+    pub fn write_bit(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+
+        self.set_bit(self.len, bit);
+        self.len += 1;
+    }
+
+    /// Writes the lowest `n` bits of `value`.
+    pub fn write_bits(&mut self, value: u64, n: usize) {
+        for i in (0..n).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    /// Overwrites `n` bits that were already written, starting at `position`. This is useful to
+    /// fill in a length field once the length is known.
+    pub fn set_bits(&mut self, position: usize, value: u64, n: usize) {
+        assert!(position + n <= self.len, "bits have not been written yet");
+
+        for i in 0..n {
+            self.set_bit(position + i, (value >> (n - 1 - i)) & 1 == 1);
+        }
+    }
+
+    fn set_bit(&mut self, position: usize, bit: bool) {
+        let mask = 0x80 >> (position % 8);
+
+        if bit {
+            self.bytes[position / 8] |= mask;
+        } else {
+            self.bytes[position / 8] &= !mask;
+        }
+    }
+
+    /// Pads with zeros until the position is a multiple of `bits`.
+    pub fn align(&mut self, bits: usize) {
+        assert!(bits > 0, "cannot align to 0 bits");
+
+        while !self.len.is_multiple_of(bits) {
+            self.write_bit(false);
+        }
+    }
+
+    /// Formats the bits as uppercase hexadecimal digits, the last digit is padded with zeros.
+    pub fn to_hex(&self) -> String {
+        let digits = self.len.div_ceil(4);
+
+        (0..digits)
+            .map(|i| {
+                let nibble = (self.bytes[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+                char::from_digit(nibble as u32, 16)
+                    .unwrap()
+                    .to_ascii_uppercase()
+            })
+            .collect()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn into_reader(self) -> BitReader {
+        BitReader {
+            bytes: self.bytes,
+            len: self.len,
+            position: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn test_bits() {
+        let mut reader = BitReader::from_hex("D2FE28").unwrap();
+        assert_eq!(reader.len(), 24);
+        assert_eq!(reader.read_bits(3).unwrap(), 6);
+        assert_eq!(reader.read_bits(3).unwrap(), 4);
+        assert!(reader.read_bit().unwrap());
+        assert_eq!(reader.position(), 7);
+        reader.align(4);
+        assert_eq!(reader.remaining(), 16);
+        assert_eq!(reader.read_bits(16).unwrap(), 0xfe28);
+        assert!(reader.read_bit().is_err());
+        assert!(BitReader::from_hex("12G").is_err());
+
+        let mut reader = BitReader::from_binary("10110").unwrap();
+        assert_eq!(reader.read_bits(5).unwrap(), 22);
+        assert!(BitReader::from_binary("102").is_err());
+
+        let mut reader = BitReader::from_bytes(&[0xab, 0xcd]);
+        assert_eq!(reader.read_bits(12).unwrap(), 0xabc);
+        assert!(reader.read_bits(5).is_err());
+
+        let mut writer = BitWriter::new();
+        writer.write_bits(6, 3);
+        writer.write_bits(0, 8);
+        writer.write_bits(0x2b, 6);
+        writer.set_bits(3, 0xa5, 8);
+        assert_eq!(writer.position(), 17);
+        assert_eq!(writer.to_hex(), "D4B58");
+        writer.align(8);
+        assert_eq!(writer.into_bytes(), [0xd4, 0xb5, 0x80]);
+
+        // There is no position that is a multiple of zero bits.
+        assert!(catch_unwind(|| BitReader::from_hex("D2").unwrap().align(0)).is_err());
+        assert!(catch_unwind(|| {
+            let mut writer = BitWriter::new();
+            writer.write_bits(1, 1);
+            writer.align(0);
+        })
+        .is_err());
+    }
+}
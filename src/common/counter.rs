@@ -0,0 +1,173 @@
+//! Multiset that counts how often every item occurs.
+use super::*;
+use std::collections::hash_map;
+use std::hash::Hash;
+use std::iter::Sum;
+use std::ops::AddAssign;
+
+/// Unsigned integer type that a [`Counter`] counts with.
+pub trait Count: Copy + Ord + Default + AddAssign + Sum + From<u8> {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_count {
+    ($($t:ty),*) => {
+        $(
+            impl Count for $t {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_count!(u8, u32, u64, u128, usize);
+
+/// Counts of type `N`, which is `usize` unless counts may grow larger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter<T: Hash + Eq, N = usize> {
+    counts: HashMap<T, N>,
+}
+
+impl<T: Hash + Eq, N> Default for Counter<T, N> {
+    fn default() -> Self {
+        Self {
+            counts: HashMap::default(),
+        }
+    }
+}
+
+impl<T: Hash + Eq> Counter<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: Hash + Eq, N: Count> Counter<T, N> {
+    pub fn add(&mut self, item: T) {
+        self.add_n(item, N::from(1));
+    }
+
+    pub fn add_n(&mut self, item: T, n: N) {
+        *self.counts.entry(item).or_default() += n;
+    }
+
+    /// Like [`Counter::add_n`], but fails instead of overflowing.
+    pub fn try_add_n(&mut self, item: T, n: N) -> Result {
+        let count = self.counts.entry(item).or_default();
+        *count = count.checked_add(n).context("count overflows")?;
+        Ok(())
+    }
+
+    /// Number of times `item` was added, zero if it was never added.
+    pub fn get(&self, item: &T) -> N {
+        self.counts.get(item).copied().unwrap_or_default()
+    }
+
+    /// Number of distinct items.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Number of items, counting every occurrence.
+    pub fn total(&self) -> N {
+        self.counts.values().copied().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, N)> {
+        self.counts.iter().map(|(item, &n)| (item, n))
+    }
+
+    /// The `k` items that occur most often, in decreasing order of their counts.
+    pub fn most_common(&self, k: usize) -> Vec<(&T, N)> {
+        self.iter()
+            .sorted_by_key(|&(_, n)| std::cmp::Reverse(n))
+            .take(k)
+            .collect()
+    }
+
+    /// An item that occurs least often.
+    pub fn min(&self) -> Option<(&T, N)> {
+        self.iter().min_by_key(|&(_, n)| n)
+    }
+
+    /// An item that occurs most often.
+    pub fn max(&self) -> Option<(&T, N)> {
+        self.iter().max_by_key(|&(_, n)| n)
+    }
+
+    /// Adds all items of `that` to this counter.
+    pub fn merge(&mut self, that: Self) {
+        for (item, n) in that {
+            self.add_n(item, n);
+        }
+    }
+}
+
+impl<T: Hash + Eq, N: Count> Extend<T> for Counter<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+impl<T: Hash + Eq, N: Count> FromIterator<T> for Counter<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut result = Self::default();
+        result.extend(iter);
+        result
+    }
+}
+
+impl<T: Hash + Eq, N> IntoIterator for Counter<T, N> {
+    type Item = (T, N);
+    type IntoIter = hash_map::IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.counts.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter() {
+        let mut counter: Counter<char> = "abracadabra".chars().collect();
+        assert_eq!(counter.get(&'a'), 5);
+        assert_eq!(counter.get(&'z'), 0);
+        assert_eq!((counter.len(), counter.total()), (5, 11));
+        assert_eq!(
+            counter.most_common(3).iter().map(|&(_, n)| n).collect_vec(),
+            [5, 2, 2]
+        );
+        assert_eq!(counter.max(), Some((&'a', 5)));
+        assert_eq!(counter.min().map(|(_, n)| n), Some(1));
+
+        counter.merge("zz".chars().collect());
+        counter.add_n('d', 3);
+        assert_eq!(counter.get(&'z'), 2);
+        assert_eq!(counter.most_common(2), [(&'a', 5), (&'d', 4)]);
+
+        counter.clear();
+        assert!(counter.is_empty());
+        assert_eq!(counter.max(), None);
+
+        let mut counter = Counter::<char, u8>::default();
+        counter.try_add_n('a', 200).unwrap();
+        assert!(counter.try_add_n('a', 100).is_err());
+        counter.add('b');
+        assert_eq!((counter.get(&'a'), counter.total()), (200, 201));
+    }
+}
@@ -0,0 +1,173 @@
+//! Detection of cycles and fixpoints in deterministic simulations, given by an initial state and a
+//! step function, so the state after a huge number of steps can be found by skipping whole cycles.
+use super::*;
+use std::hash::{Hash, Hasher};
+
+/// States visited by a simulation until the first repetition. The states from index `start`
+/// onwards repeat forever, so `states[start + length]` would be `states[start]` again.
+#[derive(Debug, Clone)]
+pub struct Cycle<S> {
+    states: Vec<S>,
+    start: usize,
+}
+
+impl<S> Cycle<S> {
+    /// Number of steps before the first state of the cycle is reached.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Number of steps after which the states repeat.
+    pub fn length(&self) -> usize {
+        self.states.len() - self.start
+    }
+
+    /// Whether the simulation comes to a halt, i.e., the step function no longer changes the state.
+    pub fn is_fixpoint(&self) -> bool {
+        self.length() == 1
+    }
+
+    /// All distinct states, starting with the initial state.
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// Index into [`Cycle::states`] of the state after `steps` steps.
+    pub fn index(&self, steps: usize) -> usize {
+        cycle_index(self.start, self.length(), steps)
+    }
+
+    /// The state after `steps` steps.
+    pub fn nth(&self, steps: usize) -> &S {
+        &self.states[self.index(steps)]
+    }
+
+    /// Sums `fun` over the states reached after each of the first `steps` steps, the initial state
+    /// is not included.
+    pub fn sum_over<F>(&self, steps: usize, mut fun: F) -> u64
+    where
+        F: FnMut(&S) -> u64,
+    {
+        let values = self.states.iter().map(&mut fun).collect_vec();
+        let sum = |range: std::ops::Range<usize>| values[range].iter().sum::<u64>();
+
+        if steps < self.states.len() {
+            return sum(1..steps + 1);
+        }
+
+        // Steps 1 until the end of the first round, then whole cycles, then the remaining part.
+        let cycles = (steps - self.start) / self.length();
+        let rest = self.index(steps);
+        let first = sum(1..self.states.len());
+        let middle = (cycles as u64 - 1) * sum(self.start..self.states.len());
+
+        first + middle + sum(self.start..rest + 1)
+    }
+}
+
+fn cycle_index(start: usize, length: usize, steps: usize) -> usize {
+    if steps < start {
+        steps
+    } else {
+        start + (steps - start) % length
+    }
+}
+
+fn hash_state<S: Hash>(state: &S) -> u64 {
+    let mut hasher = fnv::FnvHasher::default();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Runs the simulation for at most `limit` steps, returns the visited states and the index of the
+/// first state that is reached again, if any. Only hashes are stored next to the states.
+fn simulate<S, F>(initial: S, limit: usize, mut step: F) -> (Vec<S>, Option<usize>)
+where
+    S: Hash + Eq,
+    F: FnMut(&S) -> S,
+{
+    let mut indices: HashMap<u64, Vec<usize>> = HashMap::default();
+    let mut states = vec![];
+    let mut state = initial;
+
+    for steps in 0..=limit {
+        let entry = indices.entry(hash_state(&state)).or_default();
+
+        if let Some(&start) = find(entry.iter(), |&&i| states[i] == state) {
+            return (states, Some(start));
+        }
+
+        if steps == limit {
+            states.push(state);
+            break;
+        }
+
+        entry.push(steps);
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+
+    (states, None)
+}
+
+/// Runs the simulation until a state repeats. This never returns if the states keep on changing,
+/// which cannot happen if there are finitely many states.
+pub fn find_cycle<S, F>(initial: S, step: F) -> Cycle<S>
+where
+    S: Hash + Eq,
+    F: FnMut(&S) -> S,
+{
+    match simulate(initial, usize::MAX, step) {
+        (states, Some(start)) => Cycle { states, start },
+        (_, None) => unreachable!("simulation did not repeat within usize::MAX steps"),
+    }
+}
+
+/// Returns the state after `steps` steps, skipping ahead as soon as a cycle is found.
+pub fn state_after<S, F>(initial: S, steps: usize, step: F) -> S
+where
+    S: Hash + Eq,
+    F: FnMut(&S) -> S,
+{
+    let (mut states, start) = simulate(initial, steps, step);
+
+    let index = match start {
+        Some(start) => cycle_index(start, states.len() - start, steps),
+        None => steps,
+    };
+
+    states.swap_remove(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle() {
+        // 10, 5, 16, 8, 4, 2, 1, 4, 2, 1, ...
+        let collatz = |&x: &u64| if x % 2 == 0 { x / 2 } else { 3 * x + 1 };
+
+        let cycle = find_cycle(10, collatz);
+        assert_eq!((cycle.start(), cycle.length()), (4, 3));
+        assert_eq!(cycle.states(), [10, 5, 16, 8, 4, 2, 1]);
+        assert_eq!(*cycle.nth(3), 8);
+        assert_eq!(*cycle.nth(9), 1);
+        assert_eq!(*cycle.nth(1_000_000_000_000), 4);
+        assert!(!cycle.is_fixpoint());
+
+        for steps in 0..20 {
+            let expected = (1..=steps).map(|i| *cycle.nth(i)).sum::<u64>();
+            assert_eq!(cycle.sum_over(steps, |&x| x), expected);
+        }
+
+        assert_eq!(state_after(10, 2, collatz), 16);
+        assert_eq!(state_after(10, 1_000_000_000_000, collatz), 4);
+        assert_eq!(state_after(7, 0, collatz), 7);
+
+        let cycle = find_cycle(100, |&x: &u64| x.saturating_sub(30));
+        assert_eq!((cycle.start(), cycle.length()), (4, 1));
+        assert!(cycle.is_fixpoint());
+    }
+}
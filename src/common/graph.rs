@@ -0,0 +1,239 @@
+//! Graph whose nodes are identified by name, interned into dense integer IDs.
+use super::*;
+use std::collections::VecDeque;
+
+pub type NodeId = usize;
+
+/// Directed graph with an attribute of type `A` for every node. Undirected graphs store every
+/// edge in both directions, see [`Graph::connect`].
+#[derive(Debug, Clone, Default)]
+pub struct Graph<A = ()> {
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    attrs: Vec<A>,
+    edges: Vec<Vec<NodeId>>,
+}
+
+impl<A> Graph<A> {
+    pub fn new() -> Self {
+        Self {
+            names: vec![],
+            ids: HashMap::default(),
+            attrs: vec![],
+            edges: vec![],
+        }
+    }
+
+    /// Returns the ID of the node called `name`, adding it with the attribute given by `attr`
+    /// if it does not exist yet.
+    pub fn add_node<F>(&mut self, name: &str, attr: F) -> NodeId
+    where
+        F: FnOnce(&str) -> A,
+    {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.attrs.push(attr(name));
+        self.edges.push(vec![]);
+        id
+    }
+
+    /// Adds an edge from `from` to `to`.
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        self.edges[from].push(to);
+    }
+
+    /// Adds an edge in both directions.
+    pub fn connect(&mut self, a: NodeId, b: NodeId) {
+        self.add_edge(a, b);
+        self.add_edge(b, a);
+    }
+
+    /// Number of nodes.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn nodes(&self) -> std::ops::Range<NodeId> {
+        0..self.len()
+    }
+
+    pub fn node(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn attr(&self, id: NodeId) -> &A {
+        &self.attrs[id]
+    }
+
+    pub fn neighbors(&self, id: NodeId) -> &[NodeId] {
+        &self.edges[id]
+    }
+
+    /// Nodes reachable from `start` in depth-first order.
+    pub fn dfs(&self, start: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut visited = vec![false; self.len()];
+        let mut stack = vec![start];
+
+        std::iter::from_fn(move || {
+            while let Some(id) = stack.pop() {
+                if !visited[id] {
+                    visited[id] = true;
+                    stack.extend(self.edges[id].iter().rev());
+                    return Some(id);
+                }
+            }
+
+            None
+        })
+    }
+
+    /// Nodes reachable from `start` in breadth-first order, with their distance to `start`.
+    pub fn bfs(&self, start: NodeId) -> impl Iterator<Item = (NodeId, usize)> + '_ {
+        let mut visited = vec![false; self.len()];
+        let mut queue = VecDeque::from([(start, 0)]);
+        visited[start] = true;
+
+        std::iter::from_fn(move || {
+            let (id, distance) = queue.pop_front()?;
+
+            for &next in &self.edges[id] {
+                if !visited[next] {
+                    visited[next] = true;
+                    queue.push_back((next, distance + 1));
+                }
+            }
+
+            Some((id, distance))
+        })
+    }
+
+    /// All paths from `start` to `end` along which `visit` allows every step. It is given the state
+    /// of the path so far and the next node, and returns the state after the step or `None` if the
+    /// path may not continue there. `init` is the state of the path that only contains `start`.
+    /// The states must rule out cycles, or there are infinitely many paths.
+    pub fn paths<'a, S, F>(
+        &'a self,
+        start: NodeId,
+        end: NodeId,
+        init: S,
+        mut visit: F,
+    ) -> impl Iterator<Item = Vec<NodeId>> + 'a
+    where
+        S: 'a,
+        F: FnMut(&S, NodeId) -> Option<S> + 'a,
+    {
+        // Every node on the current path, with the state after reaching it and the next edge to
+        // follow from it.
+        let mut stack = vec![(start, init, 0)];
+
+        std::iter::from_fn(move || {
+            while let Some((id, state, index)) = stack.last_mut() {
+                let id = *id;
+
+                if id == end && *index == 0 {
+                    // Report the path once, then backtrack.
+                    *index = usize::MAX;
+                    return Some(stack.iter().map(|&(id, _, _)| id).collect());
+                }
+
+                match self.edges[id].get(*index) {
+                    Some(&next) if id != end => {
+                        *index += 1;
+
+                        if let Some(state) = visit(state, next) {
+                            stack.push((next, state, 0));
+                        }
+                    }
+                    _ => {
+                        stack.pop();
+                    }
+                }
+            }
+
+            None
+        })
+    }
+
+    /// All paths from `start` to `end` that visit every node at most once, except for the nodes
+    /// for which `revisit` returns true. These must not form a cycle, or there are infinitely
+    /// many paths.
+    pub fn all_paths<'a, F>(
+        &'a self,
+        start: NodeId,
+        end: NodeId,
+        revisit: F,
+    ) -> impl Iterator<Item = Vec<NodeId>> + 'a
+    where
+        F: Fn(NodeId) -> bool + 'a,
+    {
+        let mut visited = vec![false; self.len()];
+        visited[start] = true;
+
+        self.paths(start, end, visited, move |visited, next| {
+            if visited[next] && !revisit(next) {
+                return None;
+            }
+
+            let mut visited = visited.clone();
+            visited[next] = true;
+            Some(visited)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph() {
+        let mut graph = Graph::new();
+        let ids = ["a", "B", "c", "d", "e"].map(|name| graph.add_node(name, |n| n == "B"));
+        let [a, b, c, d, e] = ids;
+
+        assert_eq!(graph.add_node("c", |_| true), c);
+        assert_eq!(graph.node("d"), Some(d));
+        assert_eq!(graph.node("x"), None);
+        assert_eq!(
+            (graph.name(b), *graph.attr(b), *graph.attr(c)),
+            ("B", true, false)
+        );
+
+        graph.connect(a, b);
+        graph.connect(b, c);
+        graph.connect(a, c);
+        graph.add_edge(c, d);
+
+        assert_eq!(graph.neighbors(c), [b, a, d]);
+        assert_eq!(graph.dfs(a).collect_vec(), [a, b, c, d]);
+        assert_eq!(graph.bfs(a).collect_vec(), [(a, 0), (b, 1), (c, 1), (d, 2)]);
+        assert_eq!(graph.dfs(e).collect_vec(), [e]);
+
+        let paths = graph.all_paths(a, d, |_| false).collect_vec();
+        assert_eq!(paths, [vec![a, b, c, d], vec![a, c, d]]);
+
+        // Revisiting c allows the detour from c to b and back.
+        let paths = graph.all_paths(a, d, |id| id == c).collect_vec();
+        assert_eq!(
+            paths,
+            [vec![a, b, c, d], vec![a, c, b, c, d], vec![a, c, d]]
+        );
+
+        // The state can limit the length of the paths.
+        let paths = graph.paths(a, d, 0, |&len, _| (len < 2).then_some(len + 1));
+        assert_eq!(paths.collect_vec(), [vec![a, c, d]]);
+    }
+}
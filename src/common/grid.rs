@@ -0,0 +1,284 @@
+//! Two-dimensional grid of cells, as found in many of the puzzle inputs.
+use super::*;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// Position of a cell as `[row, column]`.
+pub type Pos = [usize; 2];
+
+pub(super) const OFFSETS4: [[isize; 2]; 4] = [[-1, 0], [0, -1], [0, 1], [1, 0]];
+pub(super) const OFFSETS8: [[isize; 2]; 8] = [
+    [-1, -1],
+    [-1, 0],
+    [-1, 1],
+    [0, -1],
+    [0, 1],
+    [1, -1],
+    [1, 0],
+    [1, 1],
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    nrows: usize,
+    ncols: usize,
+    cells: Vec<T>,
+    toroidal: bool,
+}
+
+impl<T> Grid<T> {
+    pub fn new(nrows: usize, ncols: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_fn(nrows, ncols, |_| value.clone())
+    }
+
+    pub fn from_fn<F>(nrows: usize, ncols: usize, fun: F) -> Self
+    where
+        F: FnMut(Pos) -> T,
+    {
+        let cells = (0..nrows)
+            .flat_map(|i| (0..ncols).map(move |j| [i, j]))
+            .map(fun)
+            .collect();
+
+        Self {
+            nrows,
+            ncols,
+            cells,
+            toroidal: false,
+        }
+    }
+
+    /// Parses one cell per character, all lines must have the same length.
+    pub fn from_lines<F>(lines: Lines, mut fun: F) -> Result<Self>
+    where
+        F: FnMut(char) -> Result<T>,
+    {
+        let ncols = lines.first().map_or(0, |line| line.chars().count());
+        ensure!(ncols > 0, "empty grid");
+
+        let mut cells = Vec::with_capacity(lines.len() * ncols);
+
+        for line in lines {
+            ensure!(
+                line.chars().count() == ncols,
+                "line has invalid length: {:?}",
+                line
+            );
+
+            for c in line.chars() {
+                cells.push(fun(c)?);
+            }
+        }
+
+        Ok(Self {
+            nrows: lines.len(),
+            ncols,
+            cells,
+            toroidal: false,
+        })
+    }
+
+    /// Makes the grid wrap around at its edges, which affects neighbors and offsets.
+    pub fn toroidal(mut self, toroidal: bool) -> Self {
+        self.toroidal = toroidal;
+        self
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, [i, j]: Pos) -> bool {
+        i < self.nrows && j < self.ncols
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.contains(pos) {
+            Some(&self[pos])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self[pos])
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let ncols = self.ncols;
+        (0..self.nrows).flat_map(move |i| (0..ncols).map(move |j| [i, j]))
+    }
+
+    pub fn indexed_iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.cells[i * self.ncols..(i + 1) * self.ncols]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.ncols.max(1)).take(self.nrows)
+    }
+
+    pub fn column(&self, j: usize) -> impl Iterator<Item = &T> {
+        self.cells.iter().skip(j).step_by(self.ncols.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.ncols).map(move |j| self.column(j))
+    }
+
+    pub fn map<U, F>(&self, fun: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            cells: self.cells.iter().map(fun).collect(),
+            toroidal: self.toroidal,
+        }
+    }
+
+    /// Moves `delta` away from `pos`, returns `None` when leaving a grid that is not toroidal or
+    /// when the grid is empty.
+    pub fn offset(&self, [i, j]: Pos, [di, dj]: [isize; 2]) -> Option<Pos> {
+        let (n, m) = (self.nrows as isize, self.ncols as isize);
+        let (i, j) = (i as isize + di, j as isize + dj);
+
+        if self.toroidal && n > 0 && m > 0 {
+            Some([i.rem_euclid(n) as usize, j.rem_euclid(m) as usize])
+        } else if (0..n).contains(&i) && (0..m).contains(&j) {
+            Some([i as usize, j as usize])
+        } else {
+            None
+        }
+    }
+
+    /// The cells reached from `pos` by `offsets`, each one once. On a toroidal grid with fewer
+    /// than three rows or columns several offsets lead to the same cell, or back to `pos` itself.
+    fn neighbors<'a>(
+        &'a self,
+        pos: Pos,
+        offsets: &'a [[isize; 2]],
+    ) -> impl Iterator<Item = Pos> + 'a {
+        enumerate(offsets).filter_map(move |(k, &delta)| {
+            let next = self.offset(pos, delta)?;
+            let repeated = self.toroidal
+                && (next == pos || any(&offsets[..k], |&d| self.offset(pos, d) == Some(next)));
+
+            (!repeated).then_some(next)
+        })
+    }
+
+    /// The horizontal and vertical neighbors of `pos`.
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbors(pos, &OFFSETS4)
+    }
+
+    /// The horizontal, vertical and diagonal neighbors of `pos`.
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbors(pos, &OFFSETS8)
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, [i, j]: Pos) -> &T {
+        assert!(self.contains([i, j]), "index out of bounds: {:?}", [i, j]);
+        &self.cells[i * self.ncols + j]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, [i, j]: Pos) -> &mut T {
+        assert!(self.contains([i, j]), "index out of bounds: {:?}", [i, j]);
+        &mut self.cells[i * self.ncols + j]
+    }
+}
+
+/// Prints every row on its own line, without separators between the cells.
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in enumerate(self.rows()) {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::from_lines(&["123", "456"], |c| Ok(c.to_digit(10).unwrap())).unwrap();
+
+        assert_eq!((grid.nrows(), grid.ncols()), (2, 3));
+        assert_eq!(grid[[1, 0]], 4);
+        assert_eq!(grid.get([2, 0]), None);
+        assert_eq!(grid.to_string(), "123\n456");
+        assert_eq!(grid.rows().collect_vec(), [[1, 2, 3], [4, 5, 6]]);
+        assert_eq!(grid.column(1).collect_vec(), [&2, &5]);
+
+        assert_eq!(grid.neighbors4([0, 0]).collect_vec(), [[0, 1], [1, 0]]);
+        assert_eq!(grid.neighbors8([0, 1]).count(), 5);
+
+        // With only two rows, the cells above and below are the same, and all five other cells
+        // are diagonal or direct neighbors.
+        let grid = grid.toroidal(true);
+        assert_eq!(
+            grid.neighbors4([0, 0]).collect_vec(),
+            [[1, 0], [0, 2], [0, 1]]
+        );
+        assert_eq!(grid.neighbors8([0, 1]).count(), 5);
+        assert_eq!(grid.offset([1, 2], [1, 1]), Some([0, 0]));
+
+        let grid = Grid::new(3, 3, 0).toroidal(true);
+        assert_eq!(grid.neighbors8([0, 0]).count(), 8);
+        let grid = Grid::new(1, 1, 0).toroidal(true);
+        assert_eq!(grid.neighbors8([0, 0]).count(), 0);
+        let grid = Grid::new(0, 0, 0).toroidal(true);
+        assert_eq!(grid.offset([0, 0], [1, 1]), None);
+
+        assert!(Grid::from_lines(&["12", "3"], Ok).is_err());
+        assert!(Grid::<char>::from_lines(&[], Ok).is_err());
+    }
+}
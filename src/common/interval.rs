@@ -0,0 +1,339 @@
+//! Half-open integer intervals, sets of intervals and N-dimensional axis-aligned boxes.
+use super::*;
+use std::fmt;
+
+/// The integers `start..end`, the interval is empty if `start >= end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Self {
+        Self { start, end }
+    }
+
+    /// The integers `low..=high`.
+    pub fn inclusive(low: i64, high: i64) -> Self {
+        Self::new(low, high + 1)
+    }
+
+    pub fn len(&self) -> i64 {
+        (self.end - self.start).max(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.start <= x && x < self.end
+    }
+
+    pub fn intersection(&self, that: &Self) -> Option<Self> {
+        let result = Self::new(self.start.max(that.start), self.end.min(that.end));
+        (!result.is_empty()).then_some(result)
+    }
+
+    pub fn intersects(&self, that: &Self) -> bool {
+        self.intersection(that).is_some()
+    }
+
+    /// The parts of this interval before and after `that`, which may be empty.
+    pub fn difference(&self, that: &Self) -> [Self; 2] {
+        [
+            Self::new(self.start, self.end.min(that.start)),
+            Self::new(self.start.max(that.end), self.end),
+        ]
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// Set of integers, stored as sorted intervals that neither overlap nor touch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    /// Number of integers in the set.
+    pub fn len(&self) -> i64 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        let index = self.intervals.partition_point(|i| i.end <= x);
+        self.intervals.get(index).is_some_and(|i| i.contains(x))
+    }
+
+    /// Adds `interval`, merging it with the intervals it overlaps or touches.
+    pub fn insert(&mut self, mut interval: Interval) {
+        if interval.is_empty() {
+            return;
+        }
+
+        let first = self.intervals.partition_point(|i| i.end < interval.start);
+        let last = self.intervals.partition_point(|i| i.start <= interval.end);
+
+        if first < last {
+            interval.start = interval.start.min(self.intervals[first].start);
+            interval.end = interval.end.max(self.intervals[last - 1].end);
+        }
+
+        self.intervals.splice(first..last, [interval]);
+    }
+
+    /// Removes every integer of `interval` from the set.
+    pub fn remove(&mut self, interval: Interval) {
+        if interval.is_empty() {
+            return;
+        }
+
+        let first = self.intervals.partition_point(|i| i.end <= interval.start);
+        let last = self.intervals.partition_point(|i| i.start < interval.end);
+
+        let remaining = self.intervals[first..last]
+            .iter()
+            .flat_map(|i| i.difference(&interval))
+            .filter(|i| !i.is_empty())
+            .collect_vec();
+
+        self.intervals.splice(first..last, remaining);
+    }
+
+    /// The parts of `interval` that are in the set.
+    pub fn overlap(&self, interval: Interval) -> impl Iterator<Item = Interval> + '_ {
+        let first = self.intervals.partition_point(|i| i.end <= interval.start);
+        let last = self.intervals.partition_point(|i| i.start < interval.end);
+
+        self.intervals[first..last.max(first)]
+            .iter()
+            .filter_map(move |i| i.intersection(&interval))
+    }
+
+    pub fn union(&self, that: &Self) -> Self {
+        let mut result = self.clone();
+        for &interval in &that.intervals {
+            result.insert(interval);
+        }
+        result
+    }
+
+    pub fn difference(&self, that: &Self) -> Self {
+        let mut result = self.clone();
+        for &interval in &that.intervals {
+            result.remove(interval);
+        }
+        result
+    }
+
+    pub fn intersection(&self, that: &Self) -> Self {
+        that.intervals
+            .iter()
+            .flat_map(|&interval| self.overlap(interval))
+            .collect()
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> Self {
+        let mut result = Self::new();
+        for interval in iter {
+            result.insert(interval);
+        }
+        result
+    }
+}
+
+/// Axis-aligned box in `N` dimensions, the product of one interval along every axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Aabb<const N: usize> {
+    pub axes: [Interval; N],
+}
+
+impl<const N: usize> Aabb<N> {
+    pub fn new(axes: [Interval; N]) -> Self {
+        Self { axes }
+    }
+
+    /// The box containing the points from `low` up to and including `high`.
+    pub fn inclusive(low: [i64; N], high: [i64; N]) -> Self {
+        Self::new(std::array::from_fn(|i| {
+            Interval::inclusive(low[i], high[i])
+        }))
+    }
+
+    /// Number of integer points in the box.
+    pub fn volume(&self) -> i64 {
+        self.axes.iter().map(Interval::len).product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        any(&self.axes, Interval::is_empty)
+    }
+
+    pub fn contains(&self, point: [i64; N]) -> bool {
+        all(self.axes.iter().zip(point), |(axis, x)| axis.contains(x))
+    }
+
+    pub fn intersection(&self, that: &Self) -> Option<Self> {
+        let mut axes = self.axes;
+
+        for (axis, other) in axes.iter_mut().zip(&that.axes) {
+            *axis = axis.intersection(other)?;
+        }
+
+        Some(Self::new(axes))
+    }
+
+    pub fn intersects(&self, that: &Self) -> bool {
+        self.intersection(that).is_some()
+    }
+
+    /// Splits the part of this box outside `that` into at most `2 * N` disjoint boxes.
+    pub fn subtract(&self, that: &Self) -> Vec<Self> {
+        let common = match self.intersection(that) {
+            Some(common) => common,
+            None => return vec![*self],
+        };
+
+        // Cut off the slabs before and after `that` one axis at a time, the rest of the box
+        // shrinks to the intersection.
+        let mut result = vec![];
+        let mut rest = *self;
+
+        for axis in 0..N {
+            for part in rest.axes[axis].difference(&common.axes[axis]) {
+                if !part.is_empty() {
+                    let mut slab = rest;
+                    slab.axes[axis] = part;
+                    result.push(slab);
+                }
+            }
+
+            rest.axes[axis] = common.axes[axis];
+        }
+
+        result
+    }
+}
+
+/// Number of integer points covered by at least one of the boxes.
+pub fn union_volume<const N: usize>(boxes: &[Aabb<N>]) -> i64 {
+    let mut disjoint: Vec<Aabb<N>> = vec![];
+
+    for b in boxes {
+        let mut pieces = vec![*b];
+
+        for d in &disjoint {
+            if any(&pieces, |p| p.intersects(d)) {
+                pieces = pieces.iter().flat_map(|p| p.subtract(d)).collect();
+            }
+        }
+
+        disjoint.extend(pieces.into_iter().filter(|p| !p.is_empty()));
+    }
+
+    disjoint.iter().map(Aabb::volume).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval() {
+        let a = Interval::new(0, 10);
+        assert_eq!(a.len(), 10);
+        assert_eq!(Interval::inclusive(3, 1).len(), 0);
+        assert_eq!(
+            a.intersection(&Interval::new(5, 15)),
+            Some(Interval::new(5, 10))
+        );
+        assert_eq!(a.intersection(&Interval::new(10, 15)), None);
+        assert_eq!(
+            a.difference(&Interval::new(3, 5)),
+            [Interval::new(0, 3), Interval::new(5, 10)]
+        );
+
+        let mut set: IntervalSet = [Interval::new(0, 3), Interval::new(5, 8)]
+            .into_iter()
+            .collect();
+        set.insert(Interval::new(3, 4));
+        assert_eq!(set.intervals(), [Interval::new(0, 4), Interval::new(5, 8)]);
+        set.insert(Interval::new(2, 6));
+        assert_eq!(set.intervals(), [Interval::new(0, 8)]);
+        set.remove(Interval::new(2, 4));
+        assert_eq!(set.intervals(), [Interval::new(0, 2), Interval::new(4, 8)]);
+        assert_eq!(set.len(), 6);
+        assert!(set.contains(1) && !set.contains(2) && set.contains(7) && !set.contains(8));
+
+        let other: IntervalSet = [Interval::new(-5, 1), Interval::new(6, 7)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            set.union(&other).intervals(),
+            [Interval::new(-5, 2), Interval::new(4, 8)]
+        );
+        assert_eq!(
+            set.difference(&other).intervals(),
+            [
+                Interval::new(1, 2),
+                Interval::new(4, 6),
+                Interval::new(7, 8)
+            ]
+        );
+        assert_eq!(
+            set.intersection(&other).intervals(),
+            [Interval::new(0, 1), Interval::new(6, 7)]
+        );
+        assert_eq!(
+            set.overlap(Interval::new(1, 5)).collect_vec(),
+            [Interval::new(1, 2), Interval::new(4, 5)]
+        );
+        assert_eq!(set.overlap(Interval::new(2, 4)).count(), 0);
+        assert_eq!(set.overlap(Interval::new(3, 3)).count(), 0);
+    }
+
+    #[test]
+    fn test_aabb() {
+        let a = Aabb::inclusive([0, 0, 0], [9, 9, 9]);
+        let b = Aabb::inclusive([5, -5, 3], [14, 4, 6]);
+        assert_eq!(a.volume(), 1000);
+        assert!(a.contains([9, 0, 5]) && !a.contains([10, 0, 5]));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Aabb::inclusive([5, 0, 3], [9, 4, 6]))
+        );
+
+        let parts = a.subtract(&b);
+        assert!(parts.len() <= 6);
+        assert_eq!(sum(map(&parts, Aabb::volume)), 1000 - 5 * 5 * 4);
+        assert!(all(&parts, |p| !p.intersects(&b)));
+        assert!(all(parts.iter().tuple_combinations(), |(p, q)| !p.intersects(q)));
+
+        assert_eq!(union_volume(&[a, b]), 1000 + 10 * 10 * 4 - 5 * 5 * 4);
+        assert_eq!(
+            union_volume(&[Aabb::inclusive([0], [4]), Aabb::inclusive([2], [7])]),
+            8
+        );
+    }
+}
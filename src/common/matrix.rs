@@ -0,0 +1,187 @@
+//! Square integer matrices, to evaluate linear recurrences with exponentiation by squaring.
+use super::*;
+use std::ops::{Index, IndexMut};
+
+/// Square matrix of `u128` entries. Without a modulus, arithmetic is checked and fails on
+/// overflow. With a modulus, all entries are reduced and arithmetic never overflows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    size: usize,
+    entries: Vec<u128>,
+    modulus: Option<u64>,
+}
+
+impl Matrix {
+    pub fn zeros(size: usize) -> Self {
+        Self {
+            size,
+            entries: vec![0; size * size],
+            modulus: None,
+        }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        Self::from_fn(size, |i, j| (i == j) as u128)
+    }
+
+    pub fn from_fn<F>(size: usize, mut fun: F) -> Self
+    where
+        F: FnMut(usize, usize) -> u128,
+    {
+        let mut output = Self::zeros(size);
+
+        for i in 0..size {
+            for j in 0..size {
+                output[(i, j)] = fun(i, j);
+            }
+        }
+
+        output
+    }
+
+    /// Reduces all entries modulo `modulus`, as well as the results of all further arithmetic.
+    pub fn with_modulus(mut self, modulus: u64) -> Self {
+        assert!(modulus > 0, "modulus must be positive");
+
+        for x in &mut self.entries {
+            *x %= modulus as u128;
+        }
+
+        self.modulus = Some(modulus);
+        self
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn modulus(&self) -> Option<u64> {
+        self.modulus
+    }
+
+    fn add(&self, a: u128, b: u128) -> Result<u128> {
+        match self.modulus {
+            Some(m) => Ok((a + b) % m as u128),
+            None => a.checked_add(b).context("overflow in matrix arithmetic"),
+        }
+    }
+
+    fn mul(&self, a: u128, b: u128) -> Result<u128> {
+        match self.modulus {
+            // Reduced entries are below 2^64, so their product fits.
+            Some(m) => Ok(a * b % m as u128),
+            None => a.checked_mul(b).context("overflow in matrix arithmetic"),
+        }
+    }
+
+    /// Matrix product, using the modulus of `self`.
+    pub fn checked_mul(&self, that: &Self) -> Result<Self> {
+        ensure!(
+            self.size == that.size,
+            "cannot multiply matrices of sizes {} and {}",
+            self.size,
+            that.size
+        );
+
+        let mut output = Self::zeros(self.size);
+        output.modulus = self.modulus;
+
+        for i in 0..self.size {
+            for k in 0..self.size {
+                let a = self[(i, k)];
+                if a == 0 {
+                    continue;
+                }
+
+                for j in 0..self.size {
+                    let product = self.mul(a, that[(k, j)])?;
+                    output[(i, j)] = self.add(output[(i, j)], product)?;
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Product of the matrix with a column vector.
+    pub fn apply(&self, vector: &[u128]) -> Result<Vec<u128>> {
+        ensure!(
+            vector.len() == self.size,
+            "cannot multiply matrix of size {} with vector of length {}",
+            self.size,
+            vector.len()
+        );
+
+        (0..self.size)
+            .map(|i| {
+                (0..self.size).try_fold(0, |sum, j| {
+                    let product = self.mul(self[(i, j)], vector[j])?;
+                    self.add(sum, product)
+                })
+            })
+            .collect()
+    }
+
+    /// The matrix raised to the power `exp`, using `O(log exp)` multiplications.
+    pub fn pow(&self, mut exp: u64) -> Result<Self> {
+        let mut output = Self::identity(self.size);
+        output.modulus = self.modulus;
+        let mut base = self.clone();
+
+        while exp > 0 {
+            if exp & 1 == 1 {
+                output = output.checked_mul(&base)?;
+            }
+
+            exp >>= 1;
+            if exp > 0 {
+                base = base.checked_mul(&base)?;
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = u128;
+
+    fn index(&self, (i, j): (usize, usize)) -> &u128 {
+        assert!(i < self.size && j < self.size, "index out of bounds");
+        &self.entries[i * self.size + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut u128 {
+        assert!(i < self.size && j < self.size, "index out of bounds");
+        &mut self.entries[i * self.size + j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let fib = Matrix::from_fn(2, |i, j| (i + j < 2) as u128);
+        assert_eq!(fib.pow(0).unwrap(), Matrix::identity(2));
+        assert_eq!(fib.pow(10).unwrap()[(0, 1)], 55);
+
+        let f100 = fib.pow(100).unwrap()[(0, 1)];
+        assert_eq!(f100, 354224848179261915075);
+        assert_eq!(fib.apply(&[1, 0]).unwrap(), [1, 1]);
+        assert!(fib.pow(200).is_err());
+
+        let modulus = 1_000_000_007;
+        let fib = fib.with_modulus(modulus);
+        assert_eq!(fib.pow(100).unwrap()[(0, 1)], f100 % modulus as u128);
+
+        // Doubling the exponent squares the matrix.
+        let a = fib.pow(1_000_000_000_000_000).unwrap();
+        let b = fib.pow(2_000_000_000_000_000).unwrap();
+        assert_eq!(a.checked_mul(&a).unwrap(), b);
+        assert!(a.checked_mul(&Matrix::zeros(3)).is_err());
+    }
+}
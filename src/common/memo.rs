@@ -0,0 +1,123 @@
+//! Cache for recursive solvers, keyed either by hashing or by a dense index into a vector.
+use super::*;
+use std::hash::Hash;
+
+enum Storage<K, V> {
+    Hashed(HashMap<K, V>),
+    Dense {
+        values: Vec<Option<V>>,
+        index: Box<dyn Fn(&K) -> usize>,
+    },
+}
+
+/// Remembers the value computed for every key, see [`Memo::get_or_compute`].
+pub struct Memo<K, V> {
+    storage: Storage<K, V>,
+}
+
+impl<K: Hash + Eq, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V> Memo<K, V> {
+    /// Stores the values in a hash map, any hashable key can be used.
+    pub fn new() -> Self {
+        Self {
+            storage: Storage::Hashed(HashMap::default()),
+        }
+    }
+}
+
+impl<K, V> Memo<K, V> {
+    /// Stores the values in a vector of length `len`, `index` must map every key to a distinct
+    /// position below `len`. This is faster than hashing if the keys are small and dense.
+    pub fn dense<F>(len: usize, index: F) -> Self
+    where
+        F: Fn(&K) -> usize + 'static,
+    {
+        Self {
+            storage: Storage::Dense {
+                values: (0..len).map(|_| None).collect(),
+                index: Box::new(index),
+            },
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V>
+    where
+        K: Hash + Eq,
+    {
+        match &self.storage {
+            Storage::Hashed(values) => values.get(key),
+            Storage::Dense { values, index } => values[index(key)].as_ref(),
+        }
+    }
+
+    pub fn contains(&self, key: &K) -> bool
+    where
+        K: Hash + Eq,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V)
+    where
+        K: Hash + Eq,
+    {
+        match &mut self.storage {
+            Storage::Hashed(values) => {
+                values.insert(key, value);
+            }
+            Storage::Dense { values, index } => {
+                values[index(&key)] = Some(value);
+            }
+        }
+    }
+
+    /// Returns the cached value for `key`, or computes and caches it. `fun` receives the memo
+    /// itself, so it can recursively compute the values it depends on.
+    pub fn get_or_compute<F>(&mut self, key: K, fun: F) -> V
+    where
+        K: Hash + Eq,
+        V: Clone,
+        F: FnOnce(&mut Self, &K) -> V,
+    {
+        if let Some(value) = self.get(&key) {
+            return value.clone();
+        }
+
+        let value = fun(self, &key);
+        self.insert(key, value.clone());
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fibonacci(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+        memo.get_or_compute(n, |memo, &n| match n {
+            0 | 1 => n,
+            _ => fibonacci(memo, n - 1) + fibonacci(memo, n - 2),
+        })
+    }
+
+    #[test]
+    fn test_memo() {
+        let mut memo = Memo::new();
+        assert_eq!(fibonacci(&mut memo, 90), 2880067194370816120);
+        assert_eq!(memo.get(&10), Some(&55));
+
+        let mut memo = Memo::dense(91, |&n| n as usize);
+        assert!(!memo.contains(&0));
+        assert_eq!(fibonacci(&mut memo, 90), 2880067194370816120);
+
+        // A cached value of zero is still a cached value.
+        assert_eq!(memo.get(&0), Some(&0));
+        memo.insert(0, 1);
+        assert_eq!(fibonacci(&mut memo, 0), 1);
+    }
+}
@@ -0,0 +1,146 @@
+//! Helpers for loops whose iterations are independent, which run on all available cores.
+//!
+//! The items are split into chunks that the threads take one at a time, so uneven work is spread
+//! evenly. The results are the same as those of the serial versions, in the same order.
+use super::*;
+use std::iter::Sum;
+use std::panic::resume_unwind;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Number of chunks per thread, more chunks balance the work better but add overhead.
+const CHUNKS_PER_THREAD: usize = 8;
+
+fn num_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Applies `fun` to consecutive chunks of `items` on `threads` threads, together with the index
+/// of the first item of the chunk. Returns the results in the order of the chunks.
+fn par_chunks<T, R, F>(items: &[T], threads: usize, fun: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(usize, &[T]) -> R + Sync,
+{
+    let threads = threads.min(items.len());
+    if threads <= 1 {
+        return vec![fun(0, items)];
+    }
+
+    let size = items.len().div_ceil(threads * CHUNKS_PER_THREAD);
+    let chunks = items.chunks(size).collect_vec();
+    let next = AtomicUsize::new(0);
+    let mut results = (0..chunks.len()).map(|_| None).collect_vec();
+
+    thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut output = vec![];
+
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        match chunks.get(index) {
+                            Some(chunk) => output.push((index, fun(index * size, chunk))),
+                            None => return output,
+                        }
+                    }
+                })
+            })
+            .collect_vec();
+
+        for worker in workers {
+            for (index, result) in worker.join().unwrap_or_else(|e| resume_unwind(e)) {
+                results[index] = Some(result);
+            }
+        }
+    });
+
+    results.into_iter().flatten().collect()
+}
+
+/// Applies `fun` to every item, the results are in the order of the items.
+pub fn par_map<T, U, F>(items: &[T], fun: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync,
+{
+    let chunks = par_chunks(items, num_threads(), |_, chunk| {
+        map(chunk, &fun).collect_vec()
+    });
+    chunks.into_iter().flatten().collect()
+}
+
+/// Sum of `fun` over all items.
+pub fn par_sum<T, S, F>(items: &[T], fun: F) -> S
+where
+    T: Sync,
+    S: Send + Sum,
+    F: Fn(&T) -> S + Sync,
+{
+    let chunks = par_chunks(items, num_threads(), |_, chunk| map(chunk, &fun).sum());
+    chunks.into_iter().sum()
+}
+
+fn max_by_key_with<T, K, F>(items: &[T], threads: usize, key: F) -> Option<&T>
+where
+    T: Sync,
+    K: Send + Ord,
+    F: Fn(&T) -> K + Sync,
+{
+    let chunks = par_chunks(items, threads, |offset, chunk| {
+        map(chunk, &key)
+            .enumerate()
+            .max_by(|a, b| a.1.cmp(&b.1))
+            .map(|(index, k)| (offset + index, k))
+    });
+
+    // The maxima of the chunks are in order, so ties again go to the last one.
+    let (index, _) = chunks.into_iter().flatten().max_by(|a, b| a.1.cmp(&b.1))?;
+    Some(&items[index])
+}
+
+/// The item with the largest key, which is the last one if several items have the largest key
+/// like for [`Iterator::max_by_key`].
+pub fn par_max_by_key<T, K, F>(items: &[T], key: F) -> Option<&T>
+where
+    T: Sync,
+    K: Send + Ord,
+    F: Fn(&T) -> K + Sync,
+{
+    max_by_key_with(items, num_threads(), key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel() {
+        let items = (0..1000u64).collect_vec();
+
+        let chunks = par_chunks(&items, 4, |offset, chunk| (offset, chunk.len()));
+        assert_eq!(chunks.len(), 32);
+        assert!(chunks
+            .iter()
+            .all(|&(offset, _)| items[offset] == offset as u64));
+        assert_eq!(chunks.iter().map(|&(_, n)| n).sum::<usize>(), 1000);
+
+        let squares = par_map(&items, |x| x * x);
+        assert_eq!(squares, map(&items, |x| x * x).collect_vec());
+        assert_eq!(par_sum(&items, |&x| x), 499500);
+
+        // Ties go to the last item, also across chunks.
+        for threads in [1, 4] {
+            let max = max_by_key_with(&items, threads, |&x| x / 100);
+            assert_eq!(max, Some(&999));
+            let max = max_by_key_with(&items, threads, |&x| x % 500 < 10);
+            assert_eq!(max, Some(&509));
+        }
+
+        assert_eq!(par_max_by_key(&[] as &[u64], |&x| x), None);
+        assert_eq!(par_map(&[] as &[u64], |&x| x), []);
+    }
+}
@@ -0,0 +1,260 @@
+//! Integer points in two and three dimensions and the 24 rotations of a cube.
+use super::*;
+use lazy_static::lazy_static;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+macro_rules! impl_point {
+    ($name:ident, $n:literal, $($field:ident),*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+        pub struct $name {
+            $(pub $field: i64),*
+        }
+
+        impl $name {
+            pub const ZERO: Self = Self { $($field: 0),* };
+
+            pub const fn new($($field: i64),*) -> Self {
+                Self { $($field),* }
+            }
+
+            pub fn to_array(self) -> [i64; $n] {
+                [$(self.$field),*]
+            }
+
+            pub fn map<F: FnMut(i64) -> i64>(self, mut fun: F) -> Self {
+                Self { $($field: fun(self.$field)),* }
+            }
+
+            pub fn abs(self) -> Self {
+                self.map(i64::abs)
+            }
+
+            pub fn signum(self) -> Self {
+                self.map(i64::signum)
+            }
+
+            /// Sum of the absolute coordinates.
+            pub fn norm1(self) -> i64 {
+                0 $(+ self.$field.abs())*
+            }
+
+            /// Largest absolute coordinate.
+            pub fn norm_max(self) -> i64 {
+                0 $(.max(self.$field.abs()))*
+            }
+
+            pub fn manhattan(self, that: Self) -> i64 {
+                (self - that).norm1()
+            }
+
+            pub fn min(self, that: Self) -> Self {
+                Self { $($field: self.$field.min(that.$field)),* }
+            }
+
+            pub fn max(self, that: Self) -> Self {
+                Self { $($field: self.$field.max(that.$field)),* }
+            }
+
+            /// The smallest box containing all points, or `None` if there are no points.
+            pub fn bounding_box<I>(points: I) -> Option<Aabb<$n>>
+            where
+                I: IntoIterator<Item = Self>,
+            {
+                let (low, high) = points
+                    .into_iter()
+                    .map(|p| (p, p))
+                    .reduce(|(a, b), (c, d)| (a.min(c), b.max(d)))?;
+
+                Some(Aabb::inclusive(low.to_array(), high.to_array()))
+            }
+        }
+
+        impl From<[i64; $n]> for $name {
+            fn from([$($field),*]: [i64; $n]) -> Self {
+                Self { $($field),* }
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, that: Self) -> Self {
+                Self { $($field: self.$field + that.$field),* }
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, that: Self) -> Self {
+                Self { $($field: self.$field - that.$field),* }
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                self.map(|x| -x)
+            }
+        }
+
+        impl Mul<i64> for $name {
+            type Output = Self;
+
+            fn mul(self, factor: i64) -> Self {
+                self.map(|x| x * factor)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, that: Self) {
+                *self = *self + that;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, that: Self) {
+                *self = *self - that;
+            }
+        }
+
+        /// Formats the coordinates separated by commas, as in most puzzle inputs.
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.to_array().iter().join(","))
+            }
+        }
+    };
+}
+
+impl_point!(Point2, 2, x, y);
+impl_point!(Point3, 3, x, y, z);
+
+/// Rotation that maps the axes of a cube onto each other, stored as a matrix whose rows are the
+/// images of the unit vectors. Every entry is -1, 0 or 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Rotation {
+    rows: [[i64; 3]; 3],
+}
+
+lazy_static! {
+    static ref ROTATIONS: Vec<Rotation> = {
+        let axes = [Point3::new(1, 0, 0), Point3::new(0, 1, 0), Point3::new(0, 0, 1)];
+        let directions = axes.iter().flat_map(|&a| [a, -a]).collect_vec();
+        let mut result = vec![];
+
+        // Pick where x and y point to, z follows from the right-hand rule.
+        for &x in &directions {
+            for &y in &directions {
+                let z = Point3::new(
+                    x.y * y.z - x.z * y.y,
+                    x.z * y.x - x.x * y.z,
+                    x.x * y.y - x.y * y.x,
+                );
+
+                if z.norm1() == 1 {
+                    result.push(Rotation {
+                        rows: [x.to_array(), y.to_array(), z.to_array()],
+                    });
+                }
+            }
+        }
+
+        result
+    };
+}
+
+impl Rotation {
+    pub const IDENTITY: Self = Self {
+        rows: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+    };
+
+    /// All 24 rotations, starting with the identity.
+    pub fn all() -> &'static [Rotation] {
+        &ROTATIONS
+    }
+
+    pub fn apply(&self, p: Point3) -> Point3 {
+        let [a, b, c] = self
+            .rows
+            .map(|row| row[0] * p.x + row[1] * p.y + row[2] * p.z);
+        Point3::new(a, b, c)
+    }
+
+    /// The rotation that first applies `that` and then `self`.
+    pub fn compose(&self, that: &Self) -> Self {
+        Self {
+            rows: std::array::from_fn(|i| {
+                std::array::from_fn(|j| (0..3).map(|k| self.rows[i][k] * that.rows[k][j]).sum())
+            }),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            rows: std::array::from_fn(|i| std::array::from_fn(|j| self.rows[j][i])),
+        }
+    }
+}
+
+impl Mul<Point3> for Rotation {
+    type Output = Point3;
+
+    fn mul(self, p: Point3) -> Point3 {
+        self.apply(p)
+    }
+}
+
+impl Mul for Rotation {
+    type Output = Rotation;
+
+    fn mul(self, that: Self) -> Self {
+        self.compose(&that)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point() {
+        let (a, b) = (Point2::new(1, -2), Point2::new(-3, 5));
+        assert_eq!(a + b, Point2::new(-2, 3));
+        assert_eq!(a - b, Point2::new(4, -7));
+        assert_eq!(a * 3, Point2::new(3, -6));
+        assert_eq!(a.manhattan(b), 11);
+        assert_eq!((a - b).norm_max(), 7);
+        assert_eq!((a - b).signum(), Point2::new(1, -1));
+        assert_eq!(a.to_string(), "1,-2");
+        assert_eq!(
+            Point2::bounding_box([a, b, Point2::ZERO]),
+            Some(Aabb::inclusive([-3, -2], [1, 5]))
+        );
+        assert_eq!(Point3::bounding_box([]), None);
+        assert_eq!(Point3::from([1, 2, 3]).norm1(), 6);
+    }
+
+    #[test]
+    fn test_rotation() {
+        let rotations = Rotation::all();
+        assert_eq!(rotations.len(), 24);
+        assert_eq!(rotations[0], Rotation::IDENTITY);
+        assert_eq!(rotations.iter().unique().count(), 24);
+
+        let p = Point3::new(1, 2, 3);
+        assert_eq!(rotations.iter().map(|r| r.apply(p)).unique().count(), 24);
+
+        for &r in rotations {
+            assert_eq!(r * r.inverse(), Rotation::IDENTITY);
+            assert_eq!(r.inverse().apply(r.apply(p)), p);
+
+            for &s in rotations {
+                assert!(rotations.contains(&(r * s)));
+                assert_eq!((r * s).apply(p), r.apply(s.apply(p)));
+            }
+        }
+    }
+}
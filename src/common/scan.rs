@@ -0,0 +1,187 @@
+//! Matching of simple line formats such as `"{i64},{i64} -> {i64},{i64}"` into typed tuples.
+use super::*;
+use std::any::type_name;
+
+/// Regular expression matching the textual representation of the given type.
+fn placeholder_regex(name: &str) -> &'static str {
+    match name {
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => r"[+-]?[0-9]+",
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => r"\+?[0-9]+",
+        "f32" | "f64" => r"[+-]?(?:[0-9]+\.?[0-9]*|\.[0-9]+)(?:[eE][+-]?[0-9]+)?",
+        "char" => r".",
+        "bool" => r"true|false",
+        _ => r".*?",
+    }
+}
+
+/// Splits a pattern into its literal text and its placeholders, given as the name between braces.
+fn split_pattern(pattern: &str) -> (Vec<&str>, Vec<&str>) {
+    let mut literals = vec![];
+    let mut names = vec![];
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        literals.push(&rest[..start]);
+        names.push(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+    }
+
+    literals.push(rest);
+    (literals, names)
+}
+
+/// Tuple of values that can be scanned from a line, see [`scan!`].
+pub trait Scan: Sized {
+    /// Names of the types in this tuple, as they are written in a pattern.
+    fn type_names() -> Vec<&'static str>;
+
+    /// Parses the fields, `fields` contains exactly one string for each element of the tuple.
+    fn parse_fields(fields: &[&str]) -> Result<Self>;
+}
+
+fn short_type_name<T>() -> &'static str {
+    let name = type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+fn parse_field<T: FromStr>(field: &str, index: usize) -> Result<T>
+where
+    T::Err: Display,
+{
+    match field.parse() {
+        Ok(v) => Ok(v),
+        Err(e) => bail!(
+            "failed to parse field {} ({:?}) as {}: {}",
+            index + 1,
+            field,
+            short_type_name::<T>(),
+            e
+        ),
+    }
+}
+
+macro_rules! impl_scan {
+    ($($name:ident: $index:tt),*) => {
+        impl<$($name),*> Scan for ($($name,)*)
+        where
+            $($name: FromStr, $name::Err: Display),*
+        {
+            fn type_names() -> Vec<&'static str> {
+                vec![$(short_type_name::<$name>()),*]
+            }
+
+            fn parse_fields(fields: &[&str]) -> Result<Self> {
+                Ok(($(parse_field::<$name>(fields[$index], $index)?,)*))
+            }
+        }
+    };
+}
+
+impl_scan!(A: 0);
+impl_scan!(A: 0, B: 1);
+impl_scan!(A: 0, B: 1, C: 2);
+impl_scan!(A: 0, B: 1, C: 2, D: 3);
+impl_scan!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_scan!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_scan!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_scan!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+/// Matches `line` against `pattern` and parses every placeholder, see [`scan!`].
+pub fn scan_str<T: Scan>(pattern: &str, line: &str) -> Result<T> {
+    let (literals, names) = split_pattern(pattern);
+    let types = T::type_names();
+
+    ensure!(
+        names.len() == types.len(),
+        "pattern {:?} has {} placeholders, but {} values are expected",
+        pattern,
+        names.len(),
+        types.len()
+    );
+
+    for (index, (&name, &ty)) in enumerate(names.iter().zip(&types)) {
+        ensure!(
+            name.is_empty() || name == ty,
+            "placeholder {} of pattern {:?} is {{{}}}, but is parsed as {}",
+            index + 1,
+            pattern,
+            name,
+            ty
+        );
+    }
+
+    let mut regex = String::from("^");
+    for (index, literal) in enumerate(&literals) {
+        regex += &regex::escape(literal);
+
+        if let Some(ty) = types.get(index) {
+            regex += &format!("({})", placeholder_regex(ty));
+        }
+    }
+    regex += "$";
+
+    let captures = match compile(&regex).captures(line) {
+        Some(c) => c,
+        None => bail!("line {:?} does not match pattern {:?}", line, pattern),
+    };
+
+    let fields = captures
+        .iter()
+        .skip(1)
+        .map(|m| m.map_or("", |m| m.as_str()))
+        .collect_vec();
+
+    T::parse_fields(&fields).with_context(|| format!("invalid line {:?}", line))
+}
+
+/// Matches a line against a pattern such as `"{i64},{i64} -> {i64},{i64}"` and returns the parsed
+/// placeholders as a tuple. The types in the pattern must match the element types of the tuple,
+/// which are usually inferred from how the result is used. Empty placeholders (`{}`) match any type.
+///
+/// ```ignore
+/// let (x0, y0, x1, y1): (i64, i64, i64, i64) = scan!("{i64},{i64} -> {i64},{i64}", line)?;
+/// ```
+#[macro_export]
+macro_rules! scan {
+    ($pattern:literal, $line:expr) => {
+        $crate::common::scan_str($pattern, $line)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_scan() {
+        let line = "0,9 -> 5,-9";
+        let result: (i64, u8, i64, i32) = scan!("{i64},{u8} -> {i64},{i32}", line).unwrap();
+        assert_eq!(result, (0, 9, 5, -9));
+
+        let (c, s, x): (char, String, f64) =
+            scan!("{char}: {String} ({})", "a: b c (1.5)").unwrap();
+        assert_eq!((c, &*s, x), ('a', "b c", 1.5));
+
+        let error = scan!("{i64},{i64}", "1;2")
+            .map(|(_, _): (i64, i64)| ())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line \"1;2\" does not match pattern \"{i64},{i64}\""
+        );
+
+        let error = scan!("{u8}", "300").map(|(_,): (u8,)| ()).unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "invalid line \"300\": failed to parse field 1 (\"300\") as u8: \
+             number too large to fit in target type"
+        );
+
+        assert!(scan!("{i64}", "1").map(|(_,): (u32,)| ()).is_err());
+        assert!(scan!("{i64} {i64}", "1 2").map(|(_,): (i64,)| ()).is_err());
+        assert!(scan!("a.b{}", "axb1").map(|(_,): (i64,)| ()).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Shortest path searches over implicit graphs, given by a start state and a successor function.
+//!
+//! Every search returns the total cost and the path from the start state up to and including the
+//! first state that satisfies the goal, or `None` if no such state is reachable.
+use super::*;
+use binary_heap_plus::BinaryHeap;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Clones the states on the path from the start state to `id`.
+fn reconstruct<S: Clone + Eq + Hash>(arena: &Arena<S>, id: StateId) -> Vec<S> {
+    map(arena.path(id), |i| arena[i].clone()).collect()
+}
+
+/// Breadth-first search, the cost is the number of steps taken.
+pub fn bfs<S, I, F, G>(start: S, mut successors: F, mut goal: G) -> Option<(usize, Vec<S>)>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+    F: FnMut(&S) -> I,
+    G: FnMut(&S) -> bool,
+{
+    let mut arena = Arena::new();
+    let mut queue = VecDeque::new();
+
+    let (id, _) = arena.insert(start, None);
+    queue.push_back((id, 0));
+
+    while let Some((id, steps)) = queue.pop_front() {
+        if goal(&arena[id]) {
+            return Some((steps, reconstruct(&arena, id)));
+        }
+
+        for next in successors(&arena[id]) {
+            let (next_id, is_new) = arena.insert(next, Some(id));
+
+            if is_new {
+                queue.push_back((next_id, steps + 1));
+            }
+        }
+    }
+
+    None
+}
+
+/// Dijkstra's algorithm, `successors` yields the neighboring states with the cost to reach them.
+pub fn dijkstra<S, C, I, F, G>(start: S, successors: F, goal: G) -> Option<(C, Vec<S>)>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+    F: FnMut(&S) -> I,
+    G: FnMut(&S) -> bool,
+{
+    astar(start, successors, |_| C::default(), goal)
+}
+
+/// A* search, `heuristic` must never overestimate the remaining cost to reach the goal and must
+/// not decrease by more than the cost of a step (which holds for the usual distance metrics).
+pub fn astar<S, C, I, F, H, G>(
+    start: S,
+    mut successors: F,
+    mut heuristic: H,
+    mut goal: G,
+) -> Option<(C, Vec<S>)>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+    F: FnMut(&S) -> I,
+    H: FnMut(&S) -> C,
+    G: FnMut(&S) -> bool,
+{
+    // For every state in the arena: the lowest known cost and whether it is finished.
+    let mut arena = Arena::new();
+    let mut costs = vec![(C::default(), false)];
+    let mut queue = BinaryHeap::new_by_key(|&(estimate, _, _): &(C, C, StateId)| Reverse(estimate));
+
+    let (id, _) = arena.insert(start, None);
+    queue.push((heuristic(&arena[id]), C::default(), id));
+
+    while let Some((_, cost, id)) = queue.pop() {
+        let index = id as usize;
+        if costs[index].1 || cost > costs[index].0 {
+            continue;
+        }
+
+        costs[index].1 = true;
+
+        if goal(&arena[id]) {
+            return Some((cost, reconstruct(&arena, id)));
+        }
+
+        for (next, step) in successors(&arena[id]) {
+            let next_cost = cost + step;
+            let (next_id, is_new) = arena.insert(next, Some(id));
+
+            if is_new {
+                costs.push((next_cost, false));
+            } else {
+                let next_index = next_id as usize;
+                if costs[next_index].1 || next_cost >= costs[next_index].0 {
+                    continue;
+                }
+
+                arena.set_parent(next_id, Some(id));
+                costs[next_index].0 = next_cost;
+            }
+
+            queue.push((next_cost + heuristic(&arena[next_id]), next_cost, next_id));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        // Reach 10 from 1 by doubling (cost 1) or incrementing (cost 3).
+        let successors = |&x: &u32| {
+            [(x * 2, 1), (x + 1, 3)]
+                .into_iter()
+                .filter(|&(x, _)| x <= 20)
+        };
+
+        assert_eq!(
+            dijkstra(1, successors, |&x| x == 10),
+            Some((6, vec![1, 2, 4, 5, 10]))
+        );
+        assert_eq!(
+            astar(1, successors, |&x| (x < 10) as u32, |&x| x == 10),
+            Some((6, vec![1, 2, 4, 5, 10]))
+        );
+        assert_eq!(dijkstra(1, successors, |&x| x == 21), None);
+
+        let successors = |&x: &u32| [x * 2, x + 1].into_iter().filter(|&x| x <= 20);
+        assert_eq!(
+            bfs(1, successors, |&x| x == 10),
+            Some((4, vec![1, 2, 4, 5, 10]))
+        );
+        assert_eq!(bfs(3, successors, |&x| x == 3), Some((0, vec![3])));
+    }
+}
@@ -0,0 +1,116 @@
+//! Disjoint-set forest over the elements `0..n`, with path compression and union by size.
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+    components: usize,
+}
+
+impl UnionFind {
+    /// Creates `n` singleton sets.
+    pub fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+            sizes: vec![1; n],
+            components: n,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Number of disjoint sets.
+    pub fn num_components(&self) -> usize {
+        self.components
+    }
+
+    /// Returns the representative of the set containing `x`.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point every element on the path directly to the root.
+        let mut x = x;
+        while x != root {
+            x = std::mem::replace(&mut self.parents[x], root);
+        }
+
+        root
+    }
+
+    /// Merges the sets containing `x` and `y`, returns `false` if they were already the same set.
+    pub fn union(&mut self, x: usize, y: usize) -> bool {
+        let (mut x, mut y) = (self.find(x), self.find(y));
+        if x == y {
+            return false;
+        }
+
+        if self.sizes[x] < self.sizes[y] {
+            swap(&mut x, &mut y);
+        }
+
+        self.parents[y] = x;
+        self.sizes[x] += self.sizes[y];
+        self.components -= 1;
+        true
+    }
+
+    pub fn same(&mut self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+
+    /// Number of elements in the set containing `x`.
+    pub fn size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.sizes[root]
+    }
+
+    /// All sets, ordered by their smallest element, each in increasing order.
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut indices = HashMap::default();
+        let mut result: Vec<Vec<usize>> = vec![];
+
+        for x in 0..self.len() {
+            let index = *indices.entry(self.find(x)).or_insert_with(|| {
+                result.push(vec![]);
+                result.len() - 1
+            });
+
+            result[index].push(x);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find() {
+        let mut sets = UnionFind::new(6);
+        assert_eq!(sets.num_components(), 6);
+
+        assert!(sets.union(0, 3));
+        assert!(sets.union(4, 3));
+        assert!(sets.union(1, 5));
+        assert!(!sets.union(0, 4));
+
+        assert!(sets.same(0, 4));
+        assert!(!sets.same(0, 1));
+        assert_eq!(sets.size(3), 3);
+        assert_eq!(sets.size(2), 1);
+        assert_eq!(sets.num_components(), 3);
+        assert_eq!(sets.components(), [vec![0, 3, 4], vec![1, 5], vec![2]]);
+    }
+}
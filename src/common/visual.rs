@@ -0,0 +1,242 @@
+//! Rendering of puzzle grids to ASCII art and to PPM/PGM image files.
+//!
+//! Images are only written to disk once an output directory has been chosen with
+//! [`set_output_dir`], otherwise [`Frames`] silently discards everything pushed to it.
+use super::*;
+use std::fs::{create_dir_all, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+/// Images smaller than this (in pixels along the longest side) are scaled up before saving.
+const MIN_SAVE_SIZE: usize = 512;
+
+lazy_static! {
+    static ref OUTPUT_DIR: Mutex<Option<PathBuf>> = Mutex::default();
+}
+
+/// Sets the directory that images and frame sequences are written to.
+pub fn set_output_dir(dir: impl Into<PathBuf>) {
+    *OUTPUT_DIR.lock().unwrap() = Some(dir.into());
+}
+
+fn output_dir() -> Option<PathBuf> {
+    OUTPUT_DIR.lock().unwrap().clone()
+}
+
+/// Returns `true` if an output directory was set, so callers can skip rendering otherwise.
+pub fn is_rendering() -> bool {
+    output_dir().is_some()
+}
+
+pub fn gray(value: u8) -> Rgb {
+    [value; 3]
+}
+
+/// Grayscale color for `value` within the range `0..=max`.
+pub fn shade(value: usize, max: usize) -> Rgb {
+    gray((value.min(max) * 255 / max.max(1)) as u8)
+}
+
+/// Distinct, reasonably bright color for the given label.
+pub fn label_color(label: usize) -> Rgb {
+    let hash = (label as u32 ^ 0x9e37_79b9).wrapping_mul(0x85eb_ca6b);
+    let [r, g, b, _] = hash.to_le_bytes();
+    [r | 0x40, g | 0x40, b | 0x40]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![BLACK; width * height],
+        }
+    }
+
+    /// Creates an image by calling `fun(x, y)` for every pixel.
+    pub fn from_fn<F>(width: usize, height: usize, mut fun: F) -> Self
+    where
+        F: FnMut(usize, usize) -> Rgb,
+    {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| fun(x, y))
+            .collect();
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Enlarges the image, every pixel becomes a `factor` by `factor` block.
+    pub fn scale(&self, factor: usize) -> Self {
+        Self::from_fn(self.width * factor, self.height * factor, |x, y| {
+            self.get(x / factor, y / factor)
+        })
+    }
+
+    pub fn is_gray(&self) -> bool {
+        all(&self.pixels, |&[r, g, b]| r == g && g == b)
+    }
+
+    fn luminance([r, g, b]: Rgb) -> u8 {
+        ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+    }
+
+    /// Renders the image as text, one character per pixel taken from `palette` (dark to bright).
+    pub fn to_ascii(&self, palette: &[char]) -> String {
+        assert!(!palette.is_empty(), "palette cannot be empty");
+
+        self.pixels
+            .chunks(self.width.max(1))
+            .take(self.height)
+            .map(|row| {
+                row.iter()
+                    .map(|&c| palette[Self::luminance(c) as usize * palette.len() / 256])
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
+
+    /// Writes the image in binary PPM format.
+    pub fn write_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.pixels.concat())
+    }
+
+    /// Writes the image in binary PGM format, colors are converted to their luminance.
+    pub fn write_pgm(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "P5\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&map(&self.pixels, |&c| Self::luminance(c)).collect_vec())
+    }
+
+    /// Saves the image, as PGM if the file name ends in `.pgm` and as PPM otherwise.
+    pub fn save(&self, path: &Path) -> Result {
+        let file = File::create(path).with_context(|| format!("failed to create: {:?}", path))?;
+        let mut out = BufWriter::new(file);
+
+        if path.extension().is_some_and(|ext| ext == "pgm") {
+            self.write_pgm(&mut out)?;
+        } else {
+            self.write_ppm(&mut out)?;
+        }
+
+        Ok(out.flush()?)
+    }
+}
+
+/// Sequence of images, saved as numbered files in a subdirectory of the output directory.
+#[derive(Debug)]
+pub struct Frames {
+    dir: Option<PathBuf>,
+    count: usize,
+}
+
+impl Frames {
+    pub fn new(name: &str) -> Result<Self> {
+        let dir = match output_dir() {
+            Some(dir) => dir.join(name),
+            None => {
+                return Ok(Self {
+                    dir: None,
+                    count: 0,
+                })
+            }
+        };
+
+        create_dir_all(&dir).with_context(|| format!("failed to create: {:?}", dir))?;
+        Ok(Self {
+            dir: Some(dir),
+            count: 0,
+        })
+    }
+
+    /// Returns `false` if no output directory was set, so callers can skip rendering entirely.
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    pub fn push(&mut self, image: &Image) -> Result {
+        let dir = match &self.dir {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+
+        write_image(dir, &format!("frame{:04}", self.count), image)?;
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// Saves a single image named `name` in the output directory, does nothing if there is none.
+pub fn save_image(name: &str, image: &Image) -> Result {
+    match output_dir() {
+        Some(dir) => {
+            create_dir_all(&dir).with_context(|| format!("failed to create: {:?}", dir))?;
+            write_image(&dir, name, image)
+        }
+        None => Ok(()),
+    }
+}
+
+fn write_image(dir: &Path, name: &str, image: &Image) -> Result {
+    let extension = if image.is_gray() { "pgm" } else { "ppm" };
+    let path = dir.join(format!("{}.{}", name, extension));
+    let factor = MIN_SAVE_SIZE / image.width.max(image.height).max(1);
+
+    image.scale(factor.max(1)).save(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image() {
+        let mut img = Image::from_fn(3, 2, |x, y| shade(x + y, 3));
+        img.set(0, 1, [255, 0, 0]);
+
+        assert_eq!(img.to_ascii(&[' ', '.', '#']), "  .\n .#");
+        assert!(!img.is_gray());
+
+        let mut ppm = vec![];
+        img.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 3 * 6);
+
+        let mut pgm = vec![];
+        img.scale(2).write_pgm(&mut pgm).unwrap();
+        assert!(pgm.starts_with(b"P5\n6 4\n255\n"));
+        assert_eq!(pgm.len(), 11 + 24);
+    }
+}
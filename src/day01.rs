@@ -1,25 +1,467 @@
 use crate::common::*;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Display};
+use std::io::BufRead;
 
-fn solve_a(numbers: &[u32]) -> usize {
-    numbers.windows(2).filter(|w| w[0] < w[1]).count()
+/// Counts how often the sum of a window of `width` readings is larger than the sum of the window
+/// `lag` readings earlier. The readings are pushed one at a time, so only the last `width + lag`
+/// of them are kept.
+#[derive(Debug, Clone)]
+struct WindowCounter {
+    width: usize,
+    lag: usize,
+    // Running sums of all readings up to each of the last `width + lag + 1` positions, they wrap
+    // around but the differences between them are still the window sums.
+    prefix: VecDeque<u64>,
+    count: usize,
 }
 
-fn solve_b(numbers: &[u32]) -> usize {
-    numbers.windows(4).filter(|w| w[0] < w[3]).count()
+impl WindowCounter {
+    fn new(width: usize, lag: usize) -> Result<Self> {
+        ensure!(width > 0, "window width must be positive");
+        ensure!(lag > 0, "window lag must be positive");
+
+        Ok(Self {
+            width,
+            lag,
+            prefix: VecDeque::from([0]),
+            count: 0,
+        })
+    }
+
+    fn push(&mut self, reading: u32) {
+        let last = *self.prefix.back().unwrap();
+        self.prefix.push_back(last.wrapping_add(reading as u64));
+
+        if self.prefix.len() > self.width + self.lag + 1 {
+            self.prefix.pop_front();
+        }
+
+        if self.prefix.len() == self.width + self.lag + 1 {
+            let p = &self.prefix;
+            let earlier = p[self.width].wrapping_sub(p[0]);
+            let later = p[self.width + self.lag].wrapping_sub(p[self.lag]);
+            self.count += (earlier < later) as usize;
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
 }
 
-pub(crate) fn run(lines: Lines) -> Result {
-    let numbers = lines
-        .iter()
-        .map(|s| s.parse::<u32>())
-        .collect::<Result<Vec<_>, _>>()
-        .with_context(|| "error while parsing input")?;
+/// Counts how often the sum of a window increases for every `(width, lag)` pair in `windows`, see
+/// [`WindowCounter`]. All windows are counted in a single pass over the readings.
+fn count_increases<I>(numbers: I, windows: &[(usize, usize)]) -> Result<Vec<usize>>
+where
+    I: IntoIterator<Item = Result<u32>>,
+{
+    let mut counters: Vec<_> =
+        map(windows, |&(width, lag)| WindowCounter::new(width, lag)).try_collect()?;
+    let mut empty = true;
+
+    for x in numbers {
+        let x = x?;
+        counters.iter_mut().for_each(|counter| counter.push(x));
+        empty = false;
+    }
+
+    ensure!(!empty, "no readings found");
+    Ok(map(counters, |counter| counter.count()).collect())
+}
+
+fn parse_line(index: usize, line: &str) -> Result<u32> {
+    line.trim()
+        .parse()
+        .with_context(|| format!("error while parsing input at line {}", index + 1))
+}
+
+/// Blank lines are skipped, like in [`run_reader`], which cannot tell the blank lines at the end of
+/// a stream from those in between.
+fn parse(lines: Lines) -> Result<Vec<u32>> {
+    enumerate(lines)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_line(i, line))
+        .collect()
+}
+
+/// The width and lag of the windows of part A and part B.
+const WINDOWS: [(usize, usize); 2] = [(1, 1), (3, 1)];
+
+/// Answers both parts in a single pass over the readings.
+fn analyze<I>(numbers: I) -> Result
+where
+    I: IntoIterator<Item = Result<u32>>,
+{
+    let counts = count_increases(numbers, &WINDOWS)?;
+
+    answer('A', counts[0]);
+    answer('B', counts[1]);
+
+    Ok(())
+}
+
+/// Parses the readings line by line while they are read, blank lines are skipped.
+fn read_numbers<R: BufRead>(reader: R) -> impl Iterator<Item = Result<u32>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| match line {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => Some(parse_line(i, &line)),
+            Err(e) => Some(Err(e.into())),
+        })
+}
+
+/// Processes the readings line by line while they are read, without keeping them in memory.
+pub(crate) fn run_reader<R: BufRead>(reader: R) -> Result {
+    let verbose = is_verbose();
+    let mut profile = None;
+
+    let numbers = read_numbers(reader).inspect(|x| {
+        if let (true, Ok(x)) = (verbose, x) {
+            Profile::push(&mut profile, *x);
+        }
+    });
+
+    analyze(numbers)?;
+
+    if let Some(profile) = profile {
+        println!("{}", profile);
+    }
+
+    Ok(())
+}
+
+/// Counts how often the sum of a window of `width` readings read from `reader` is larger than the
+/// sum of the window `lag` readings earlier.
+pub(crate) fn run_window<R: BufRead>(reader: R, width: usize, lag: usize) -> Result<usize> {
+    let counts = count_increases(read_numbers(reader), &[(width, lag)])?;
+    Ok(counts[0])
+}
+
+/// Consecutive readings `start..start + len`, with the depths at both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Run {
+    start: usize,
+    len: usize,
+    first: u32,
+    last: u32,
+}
+
+impl Run {
+    fn new(start: usize, depth: u32) -> Self {
+        Self {
+            start,
+            len: 1,
+            first: depth,
+            last: depth,
+        }
+    }
+}
+
+/// Tracks the current run of readings and the first longest one so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LongestRun {
+    best: Run,
+    current: Run,
+}
+
+impl LongestRun {
+    fn new(depth: u32) -> Self {
+        Self {
+            best: Run::new(0, depth),
+            current: Run::new(0, depth),
+        }
+    }
+
+    /// Adds reading `index`, `extends` tells whether it continues the current run.
+    fn push(&mut self, index: usize, depth: u32, extends: bool) {
+        if extends {
+            self.current.len += 1;
+            self.current.last = depth;
+        } else {
+            self.current = Run::new(index, depth);
+        }
+
+        if self.current.len > self.best.len {
+            self.best = self.current;
+        }
+    }
+}
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One character per group of readings, whose height shows the mean depth of the group. There
+/// are at most `width` groups, when another one is needed every two groups are merged into one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sparkline {
+    width: usize,
+    size: usize,
+    /// The sum and the number of readings of every group.
+    groups: Vec<(u64, usize)>,
+}
+
+impl Sparkline {
+    fn new(width: usize) -> Self {
+        Self {
+            width: width.max(1),
+            size: 1,
+            groups: vec![],
+        }
+    }
+
+    fn push(&mut self, depth: u32) {
+        if self.groups.last().is_none_or(|&(_, n)| n == self.size) {
+            if self.groups.len() == self.width {
+                self.groups = map(self.groups.chunks(2), |pair| {
+                    pair.iter()
+                        .fold((0, 0), |(sum, n), &(s, k)| (sum + s, n + k))
+                })
+                .collect();
+                self.size *= 2;
+            }
+
+            if self.groups.last().is_none_or(|&(_, n)| n == self.size) {
+                self.groups.push((0, 0));
+            }
+        }
+
+        let (sum, n) = self.groups.last_mut().unwrap();
+        *sum += depth as u64;
+        *n += 1;
+    }
+}
+
+impl Display for Sparkline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let means = map(&self.groups, |&(sum, n)| sum as f64 / n as f64).collect_vec();
+        let (low, high) = means.iter().fold((f64::MAX, f64::MIN), |(low, high), &x| {
+            (low.min(x), high.max(x))
+        });
+
+        for x in means {
+            let level = if high > low {
+                (x - low) / (high - low) * (SPARKS.len() - 1) as f64
+            } else {
+                0.0
+            };
+
+            write!(f, "{}", SPARKS[level.round() as usize])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts the readings in ranges of `size` depths that start at multiples of `size`. The size
+/// doubles whenever the readings would span more than `bins` ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Histogram {
+    bins: u64,
+    size: u64,
+    /// The number of readings in every range, by the start of the range divided by the size.
+    counts: BTreeMap<u64, usize>,
+}
+
+impl Histogram {
+    fn new(bins: usize) -> Self {
+        Self {
+            bins: bins.max(1) as u64,
+            size: 1,
+            counts: BTreeMap::new(),
+        }
+    }
 
-    let n = solve_a(&numbers);
-    println!("part A: {}", n);
+    fn push(&mut self, depth: u32) {
+        *self.counts.entry(depth as u64 / self.size).or_default() += 1;
 
-    let n = solve_b(&numbers);
-    println!("part B: {}", n);
+        while self.span() > self.bins {
+            self.size *= 2;
+            self.counts = self
+                .counts
+                .iter()
+                .fold(BTreeMap::new(), |mut counts, (&k, &n)| {
+                    *counts.entry(k / 2).or_default() += n;
+                    counts
+                });
+        }
+    }
+
+    /// Number of ranges from the lowest to the highest depth.
+    fn span(&self) -> u64 {
+        match (self.counts.first_key_value(), self.counts.last_key_value()) {
+            (Some((low, _)), Some((high, _))) => high - low + 1,
+            _ => 0,
+        }
+    }
+
+    /// The inclusive bounds of every range between the depths `low` and `high`, with its count.
+    fn bins(&self, low: u32, high: u32) -> Vec<(u32, u32, usize)> {
+        let (low, high) = (low as u64, high as u64);
+
+        map(low / self.size..=high / self.size, |k| {
+            let start = (k * self.size).max(low);
+            let end = ((k + 1) * self.size - 1).min(high);
+            let count = self.counts.get(&k).copied().unwrap_or(0);
+            (start as u32, end as u32, count)
+        })
+        .collect()
+    }
+}
+
+/// Features of the depth profile, to sanity check sonar data. The readings are added one at a
+/// time and only a bounded summary of them is kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Profile {
+    readings: usize,
+    low: u32,
+    high: u32,
+    longest_increase: LongestRun,
+    longest_plateau: LongestRun,
+    /// The position of the reading before the largest drop, and the depths before and after it.
+    largest_drop: Option<(usize, u32, u32)>,
+    sparkline: Sparkline,
+    histogram: Histogram,
+}
+
+impl Profile {
+    fn new(depth: u32) -> Self {
+        let mut profile = Self {
+            readings: 1,
+            low: depth,
+            high: depth,
+            longest_increase: LongestRun::new(depth),
+            longest_plateau: LongestRun::new(depth),
+            largest_drop: None,
+            sparkline: Sparkline::new(60),
+            histogram: Histogram::new(10),
+        };
+
+        profile.sparkline.push(depth);
+        profile.histogram.push(depth);
+        profile
+    }
+
+    /// Adds the next reading, or starts a profile with it.
+    fn push(profile: &mut Option<Self>, depth: u32) {
+        let profile = match profile {
+            Some(profile) => profile,
+            None => {
+                *profile = Some(Self::new(depth));
+                return;
+            }
+        };
+
+        let index = profile.readings;
+        let previous = profile.longest_increase.current.last;
+
+        profile.readings += 1;
+        profile.low = profile.low.min(depth);
+        profile.high = profile.high.max(depth);
+        profile
+            .longest_increase
+            .push(index, depth, previous < depth);
+        profile
+            .longest_plateau
+            .push(index, depth, previous == depth);
+        profile.sparkline.push(depth);
+        profile.histogram.push(depth);
+
+        // The first of several equally large drops is kept.
+        let drop = previous.saturating_sub(depth);
+        if drop > 0 && profile.largest_drop.is_none_or(|(_, a, b)| drop > a - b) {
+            profile.largest_drop = Some((index - 1, previous, depth));
+        }
+    }
+
+    /// Adds all readings, returns `None` if there are none.
+    fn from_readings(numbers: impl IntoIterator<Item = u32>) -> Option<Self> {
+        let mut profile = None;
+        numbers
+            .into_iter()
+            .for_each(|x| Self::push(&mut profile, x));
+        profile
+    }
+
+    /// Describes a run with the line numbers and depths at its ends.
+    fn describe(run: Run) -> String {
+        if run.len == 1 {
+            format!("1 reading at line {} ({})", run.start + 1, run.first)
+        } else {
+            format!(
+                "{} readings from line {} to {} ({} to {})",
+                run.len,
+                run.start + 1,
+                run.start + run.len,
+                run.first,
+                run.last
+            )
+        }
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "readings: {}, depth {} to {}",
+            self.readings, self.low, self.high
+        )?;
+        writeln!(
+            f,
+            "longest increase: {}",
+            Self::describe(self.longest_increase.best)
+        )?;
+        writeln!(
+            f,
+            "longest plateau: {}",
+            Self::describe(self.longest_plateau.best)
+        )?;
+
+        match self.largest_drop {
+            Some((i, from, to)) => writeln!(
+                f,
+                "largest drop: {} from line {} to {} ({} to {})",
+                from - to,
+                i + 1,
+                i + 2,
+                from,
+                to
+            )?,
+            None => writeln!(f, "largest drop: none")?,
+        }
+
+        writeln!(f, "profile: {}", self.sparkline)?;
+        write!(f, "histogram:")?;
+
+        let bins = self.histogram.bins(self.low, self.high);
+        let most = bins.iter().map(|&(_, _, n)| n).max().unwrap_or(0);
+        let ranges = map(&bins, |(start, end, _)| format!("{}..={}", start, end)).collect_vec();
+        let width = ranges.iter().map(String::len).max().unwrap_or(0);
+
+        for (range, (_, _, count)) in ranges.into_iter().zip(bins) {
+            let bar = "#".repeat((count * 40).div_ceil(most));
+            let line = format!("  {:>width$} {:>8} {}", range, count, bar, width = width);
+            write!(f, "\n{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+fn format(numbers: &[u32]) -> String {
+    numbers.iter().join("\n")
+}
+
+pub(crate) fn run(lines: Lines) -> Result {
+    let numbers = parse(lines)?;
+    analyze(numbers.iter().map(|&x| Ok(x)))?;
+
+    if let Some(profile) = Profile::from_readings(numbers).filter(|_| is_verbose()) {
+        println!("{}", profile);
+    }
 
     Ok(())
 }
@@ -28,17 +470,165 @@ pub(crate) fn run(lines: Lines) -> Result {
 mod tests {
     use super::*;
 
+    const NUMBERS: [u32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
     #[test]
     fn test_a() {
-        let numbers = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
-
-        assert_eq!(solve_a(&numbers), 7);
+        let numbers = NUMBERS.map(Ok);
+        assert_eq!(count_increases(numbers, &[(1, 1)]).unwrap(), [7]);
     }
 
     #[test]
     fn test_b() {
-        let numbers = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        let numbers = || NUMBERS.map(Ok);
+        assert_eq!(count_increases(numbers(), &WINDOWS).unwrap(), [7, 5]);
+        assert!(count_increases(numbers(), &[(0, 1)]).is_err());
+        assert!(count_increases(numbers(), &[(3, 0)]).is_err());
+        assert!(count_increases([], &[(3, 1)]).is_err());
+
+        let text = NUMBERS.iter().join("\n");
+        assert_eq!(run_window(text.as_bytes(), 3, 1).unwrap(), 5);
+        assert_eq!(run_window(text.as_bytes(), 1, 2).unwrap(), 5);
+
+        // Compare with summing every pair of windows.
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let numbers = (0..rng.random_range(1..40))
+                .map(|_| rng.random_range(0..=u32::MAX))
+                .collect_vec();
+            let (width, lag) = (rng.random_range(1..6), rng.random_range(1..6));
+
+            let sum = |i: usize| numbers[i..i + width].iter().map(|&x| x as u64).sum::<u64>();
+            let expected = (0..(numbers.len() + 1).saturating_sub(width + lag))
+                .filter(|&i| sum(i) < sum(i + lag))
+                .count();
+
+            assert_eq!(
+                count_increases(numbers.iter().map(|&x| Ok(x)), &[(width, lag)]).unwrap(),
+                [expected]
+            );
+        }
+    }
+
+    #[test]
+    fn test_profile() {
+        let profile = Profile::from_readings(NUMBERS).unwrap();
+        assert_eq!(
+            profile.longest_increase.best,
+            Run {
+                start: 0,
+                len: 4,
+                first: 199,
+                last: 210
+            }
+        );
+        assert_eq!(profile.longest_plateau.best, Run::new(0, 199));
+        assert_eq!(profile.largest_drop, Some((3, 210, 200)));
+
+        let text = profile.to_string();
+        assert!(text.contains("readings: 10, depth 199 to 269"));
+        assert!(text.contains("longest increase: 4 readings from line 1 to 4 (199 to 210)"));
+        assert!(text.contains("largest drop: 10 from line 4 to 5 (210 to 200)"));
+        assert!(text.lines().all(|line| !line.starts_with("part")));
+
+        let numbers = [5, 5, 5, 3, 3, 1, 1, 1, 1];
+        let profile = Profile::from_readings(numbers).unwrap();
+        assert_eq!(profile.longest_increase.best, Run::new(0, 5));
+        assert_eq!(
+            profile.longest_plateau.best,
+            Run {
+                start: 5,
+                len: 4,
+                first: 1,
+                last: 1
+            }
+        );
+        assert_eq!(profile.largest_drop, Some((2, 5, 3)));
+        assert_eq!(Profile::from_readings([]), None);
+
+        let sparkline = |numbers: &[u32], width| {
+            let mut sparkline = Sparkline::new(width);
+            numbers.iter().for_each(|&x| sparkline.push(x));
+            sparkline
+        };
+
+        assert_eq!(
+            sparkline(&[1, 2, 3, 4, 5, 6, 7, 8], 8).to_string(),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[1, 2, 3, 4, 5, 6, 7, 8], 4).to_string(), "▁▃▆█");
+        assert_eq!(sparkline(&[7, 7], 10).to_string(), "▁▁");
+
+        // The number of groups stays bounded however many readings there are.
+        let long = sparkline(&(0..100_000).collect_vec(), 60);
+        assert!((30..=60).contains(&long.groups.len()));
+        assert_eq!(long.to_string().chars().next(), Some('▁'));
 
-        assert_eq!(solve_b(&numbers), 5);
+        let histogram = |numbers: &[u32], bins| {
+            let mut histogram = Histogram::new(bins);
+            numbers.iter().for_each(|&x| histogram.push(x));
+            let (low, high) = (
+                *numbers.iter().min().unwrap(),
+                *numbers.iter().max().unwrap(),
+            );
+            histogram.bins(low, high)
+        };
+
+        assert_eq!(histogram(&[0, 1, 5, 9, 9], 2), [(0, 7, 3), (8, 9, 2)]);
+        assert_eq!(histogram(&[3, 3], 10), [(3, 3, 2)]);
+        assert_eq!(histogram(&[0, 10], 3), [(0, 3, 1), (4, 7, 0), (8, 10, 1)]);
+        assert_eq!(histogram(&[0, u32::MAX], 10).len(), 8);
+
+        let mut histogram = Histogram::new(10);
+        (0..100_000).for_each(|x| histogram.push(x * 7919));
+        assert!(histogram.counts.len() <= 10);
+        assert_eq!(histogram.counts.values().sum::<usize>(), 100_000);
+    }
+
+    #[test]
+    fn test_reader() {
+        let text = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+
+        take_answers();
+        run_reader(text.as_bytes()).unwrap();
+        assert_eq!(take_answers(), [('A', "7".into()), ('B', "5".into())]);
+
+        assert!(run_reader("1\nx\n".as_bytes()).is_err());
+
+        // Streamed input and input from a file treat blank lines the same way.
+        for text in ["1\n\n2\n", "\n3\n\n\n1\n", "", "\n\n"] {
+            let lines = text.trim().split('\n').collect_vec();
+            let from_lines = run(&lines).map(|_| take_answers());
+            let from_reader = run_reader(text.as_bytes()).map(|_| take_answers());
+
+            assert_eq!(from_lines.is_ok(), from_reader.is_ok(), "{:?}", text);
+            if let (Ok(a), Ok(b)) = (from_lines, from_reader) {
+                assert_eq!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let len = rng.random_range(1..50);
+            let numbers = (0..len).map(|_| rng.random()).collect_vec();
+            let text = format(&numbers);
+
+            assert_eq!(parse(&text.lines().collect_vec()).unwrap(), numbers);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "199", "200", "208", "210", "200", "207", "240", "269", "260", "263",
+            ],
+            run,
+        );
     }
 }
@@ -1,6 +1,7 @@
 use crate::common::*;
+use std::fmt::{self, Display};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum Action {
     Forward(i32),
     Down(i32),
@@ -9,6 +10,16 @@ enum Action {
 
 use Action::*;
 
+impl Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Forward(v) => write!(f, "forward {}", v),
+            Down(v) => write!(f, "down {}", v),
+            Up(v) => write!(f, "up {}", v),
+        }
+    }
+}
+
 fn parse(lines: Lines) -> Result<Vec<Action>> {
     lines
         .iter()
@@ -28,48 +39,189 @@ fn parse(lines: Lines) -> Result<Vec<Action>> {
         .collect()
 }
 
-fn solve_a(actions: &[Action]) -> i32 {
-    let mut forward = 0;
-    let mut depth = 0;
+/// How the submarine interprets the planned course. Models keep track of the position themselves
+/// and may reject an action, for example one that takes the submarine somewhere it cannot go.
+trait SubmarineModel {
+    fn apply(&mut self, action: Action) -> Result;
+
+    fn horizontal(&self) -> i64;
+
+    fn depth(&self) -> i64;
+}
+
+/// Forward moves ahead, down and up change the depth directly (part A).
+#[derive(Debug, Default)]
+struct Simple {
+    horizontal: i64,
+    depth: i64,
+}
 
-    for &action in actions {
+impl SubmarineModel for Simple {
+    fn apply(&mut self, action: Action) -> Result {
         match action {
-            Forward(v) => forward += v,
-            Down(v) => depth += v,
-            Up(v) => depth -= v,
+            Forward(v) => self.horizontal += v as i64,
+            Down(v) => self.depth += v as i64,
+            Up(v) => self.depth -= v as i64,
         }
+
+        Ok(())
+    }
+
+    fn horizontal(&self) -> i64 {
+        self.horizontal
     }
 
-    forward * depth
+    fn depth(&self) -> i64 {
+        self.depth
+    }
 }
 
-fn solve_b(actions: &[Action]) -> i32 {
-    let mut forward = 0;
-    let mut aim = 0;
-    let mut depth = 0;
+/// Down and up change the aim, forward moves ahead and changes the depth by the aim (part B).
+#[derive(Debug, Default)]
+struct Aim {
+    horizontal: i64,
+    depth: i64,
+    aim: i64,
+}
+
+impl Aim {
+    fn forward(&mut self, v: i32) -> Result {
+        let dive = (v as i64).checked_mul(self.aim);
+        self.horizontal += v as i64;
+        self.depth = dive
+            .and_then(|dive| self.depth.checked_add(dive))
+            .context("depth out of range")?;
+
+        Ok(())
+    }
+}
 
-    for &action in actions {
+impl SubmarineModel for Aim {
+    fn apply(&mut self, action: Action) -> Result {
         match action {
-            Forward(v) => {
-                forward += v;
-                depth += v * aim;
-            }
-            Down(v) => aim += v,
-            Up(v) => aim -= v,
+            Forward(v) => self.forward(v)?,
+            Down(v) => self.aim += v as i64,
+            Up(v) => self.aim -= v as i64,
         }
+
+        Ok(())
+    }
+
+    fn horizontal(&self) -> i64 {
+        self.horizontal
+    }
+
+    fn depth(&self) -> i64 {
+        self.depth
+    }
+}
+
+/// Like [`Aim`], but the water straightens the submarine out: after every forward move the aim
+/// loses a tenth of its value, rounded towards zero.
+#[derive(Debug, Default)]
+struct Drag(Aim);
+
+impl SubmarineModel for Drag {
+    fn apply(&mut self, action: Action) -> Result {
+        self.0.apply(action)?;
+
+        if let Forward(_) = action {
+            self.0.aim -= self.0.aim / 10;
+        }
+
+        Ok(())
+    }
+
+    fn horizontal(&self) -> i64 {
+        self.0.horizontal()
+    }
+
+    fn depth(&self) -> i64 {
+        self.0.depth()
+    }
+}
+
+/// Wraps another model and fails once the submarine would rise above the surface.
+#[derive(Debug, Default)]
+struct Bounded<M>(M);
+
+impl<M: SubmarineModel> SubmarineModel for Bounded<M> {
+    fn apply(&mut self, action: Action) -> Result {
+        self.0.apply(action)?;
+        ensure!(
+            self.depth() >= 0,
+            "surfaced above zero: depth {}",
+            self.depth()
+        );
+
+        Ok(())
+    }
+
+    fn horizontal(&self) -> i64 {
+        self.0.horizontal()
+    }
+
+    fn depth(&self) -> i64 {
+        self.0.depth()
+    }
+}
+
+type NewModel = fn() -> Box<dyn SubmarineModel>;
+
+fn boxed<M: SubmarineModel + Default + 'static>() -> Box<dyn SubmarineModel> {
+    Box::<M>::default()
+}
+
+/// Models that can be selected by name.
+static MODELS: [(&str, NewModel); 5] = [
+    ("simple", boxed::<Simple>),
+    ("aim", boxed::<Aim>),
+    ("drag", boxed::<Drag>),
+    ("bounded", boxed::<Bounded<Aim>>),
+    ("bounded-simple", boxed::<Bounded<Simple>>),
+];
+
+fn model(name: &str) -> Result<Box<dyn SubmarineModel>> {
+    let (_, new) = MODELS
+        .iter()
+        .find(|(model, _)| *model == name)
+        .with_context(|| {
+            let names = MODELS.iter().map(|(model, _)| model).join(", ");
+            format!("unknown model: {:?} (expected one of {})", name, names)
+        })?;
+
+    Ok(new())
+}
+
+/// Follows the course with the given model and returns the final horizontal position times the
+/// final depth.
+fn simulate(actions: &[Action], model: &mut dyn SubmarineModel) -> Result<i64> {
+    for (index, &action) in actions.iter().enumerate() {
+        model
+            .apply(action)
+            .with_context(|| format!("action {} ({})", index + 1, action))?;
     }
 
-    forward * depth
+    model
+        .horizontal()
+        .checked_mul(model.depth())
+        .context("answer out of range")
+}
+
+/// Runs day 2 on `lines` with the model called `name` and returns its answer.
+pub(crate) fn run_model(name: &str, lines: Lines) -> Result<i64> {
+    let mut model = model(name)?;
+    simulate(&parse(lines)?, model.as_mut())
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let actions = parse(lines)?;
 
-    let n = solve_a(&actions);
-    println!("part A: {}", n);
+    let n = simulate(&actions, &mut Simple::default())?;
+    answer('A', n);
 
-    let n = solve_b(&actions);
-    println!("part B: {}", n);
+    let n = simulate(&actions, &mut Aim::default())?;
+    answer('B', n);
 
     Ok(())
 }
@@ -93,11 +245,72 @@ mod tests {
 
     #[test]
     fn test_a() {
-        assert_eq!(solve_a(&input()), 150);
+        assert_eq!(simulate(&input(), &mut Simple::default()).unwrap(), 150);
     }
 
     #[test]
     fn test_b() {
-        assert_eq!(solve_b(&input()), 900);
+        assert_eq!(simulate(&input(), &mut Aim::default()).unwrap(), 900);
+    }
+
+    #[test]
+    fn test_models() {
+        let actions = input();
+        let answer = |name| simulate(&actions, model(name).unwrap().as_mut()).unwrap();
+
+        assert_eq!(answer("simple"), 150);
+        assert_eq!(answer("aim"), 900);
+        assert_eq!(answer("bounded"), 900);
+        assert_eq!(answer("bounded-simple"), 150);
+
+        // An aim of 5 loses nothing after the second forward, 10 drops to 9 after the third.
+        let mut drag = Drag::default();
+        simulate(&actions, &mut drag).unwrap();
+        assert_eq!(
+            (drag.horizontal(), drag.depth(), drag.0.aim),
+            (15, 40 + 20, 9)
+        );
+        assert_eq!(answer("drag"), 15 * 60);
+
+        assert!(model("sideways").is_err());
+
+        // Rising above the surface is an error, but only for bounded models.
+        let actions = parse(&["down 2", "forward 1", "up 5", "forward 2"]).unwrap();
+        let answer = |name| simulate(&actions, model(name).unwrap().as_mut());
+        assert_eq!(answer("aim").unwrap(), 3 * -4);
+        let error = answer("bounded").unwrap_err();
+        assert_eq!(error.to_string(), "action 4 (forward 2)");
+        assert_eq!(
+            answer("bounded-simple").unwrap_err().to_string(),
+            "action 3 (up 5)"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let v = rng.random_range(0..1000);
+            let action = [Forward(v), Down(v), Up(v)][rng.random_range(0..3)];
+            let line = action.to_string();
+
+            assert_eq!(parse(&[&line]).unwrap(), [action]);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "forward 5",
+                "down 5",
+                "forward 8",
+                "up 3",
+                "down 8",
+                "forward 2",
+            ],
+            run,
+        );
     }
 }
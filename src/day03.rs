@@ -6,10 +6,23 @@ fn parse(input: Lines) -> Result<Vec<Number>> {
     input
         .iter()
         .filter(|s| !s.is_empty())
-        .map(|line| Ok(i32::from_str_radix(line, 2)?))
+        .map(|line| {
+            let mut reader = BitReader::from_binary(line)?;
+            ensure!(reader.len() < 32, "number has too many bits: {:?}", line);
+
+            Ok(reader.read_bits(reader.len())? as Number)
+        })
         .collect()
 }
 
+#[allow(dead_code)]
+fn format(input: &[Number], n: usize) -> String {
+    input
+        .iter()
+        .map(|number| format!("{:0width$b}", number, width = n))
+        .join("\n")
+}
+
 fn solve_a(input: &[Number], n: i32) -> (Number, Number) {
     let mut gamma = 0;
 
@@ -36,7 +49,7 @@ enum Rating {
     Co2,
 }
 
-fn solve_b(input: &[Number], item: Rating, n: i32) -> Number {
+fn solve_b(input: &[Number], item: Rating, n: i32) -> Result<Number> {
     let mut input = input.to_vec();
 
     for p in (0..n).rev() {
@@ -56,22 +69,25 @@ fn solve_b(input: &[Number], item: Rating, n: i32) -> Number {
         input.retain(|&number| number & mask == bit);
 
         if input.len() == 1 {
-            return input[0];
+            return Ok(input[0]);
         }
     }
 
-    panic!("number not found!");
+    bail!("number not found!");
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let lines = parse(lines)?;
 
     let (gamma, epsilon) = solve_a(&lines, 12);
-    println!("part A: {}", gamma * epsilon);
+    answer('A', gamma * epsilon);
 
-    let oxy = solve_b(&lines, Rating::Oxygen, 12);
-    let co2 = solve_b(&lines, Rating::Co2, 12);
-    println!("part A: {}", oxy * co2);
+    let oxy = solve_b(&lines, Rating::Oxygen, 12)?;
+    let co2 = solve_b(&lines, Rating::Co2, 12)?;
+    let rating = oxy
+        .checked_mul(co2)
+        .context("life support rating overflows")?;
+    answer('B', rating);
 
     Ok(())
 }
@@ -98,9 +114,35 @@ mod tests {
 
     #[test]
     fn test_b() {
-        let oxy = solve_b(&input(), Rating::Oxygen, 5);
-        let co2 = solve_b(&input(), Rating::Co2, 5);
+        let oxy = solve_b(&input(), Rating::Oxygen, 5).unwrap();
+        let co2 = solve_b(&input(), Rating::Co2, 5).unwrap();
 
         assert_eq!((oxy, co2), (10, 23));
     }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let n = rng.random_range(1..=12);
+            let len = rng.random_range(1..50);
+            let numbers = (0..len).map(|_| rng.random_range(0..1 << n)).collect_vec();
+            let text = format(&numbers, n);
+
+            assert!(text.lines().all(|line| line.len() == n));
+            assert_eq!(parse(&text.lines().collect_vec()).unwrap(), numbers);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
+                "11001", "00010", "01010",
+            ],
+            run,
+        );
+    }
 }
@@ -20,8 +20,11 @@ fn parse_cards(lines: &[&str]) -> Result<Vec<BingoCard>> {
             let line = lines.next();
             let line = line.context("invalid input")?;
 
-            for (j, s) in enumerate(line.split_whitespace()) {
-                card[[i, j]] = s.parse().context("invalid number")?;
+            let row = parse_list::<Num>(line, ' ')?;
+            ensure!(row.len() == N, "invalid card row: {:?}", line);
+
+            for (j, x) in enumerate(row) {
+                card[[i, j]] = x;
             }
         }
 
@@ -35,12 +38,30 @@ fn parse_cards(lines: &[&str]) -> Result<Vec<BingoCard>> {
     Ok(cards)
 }
 
+#[allow(dead_code)]
+fn format_numbers(numbers: &[Num]) -> String {
+    numbers.iter().join(",")
+}
+
+#[allow(dead_code)]
+fn format_cards(cards: &[BingoCard]) -> String {
+    cards
+        .iter()
+        .map(|card| {
+            card.rows()
+                .into_iter()
+                .map(|row| row.iter().map(|x| format!("{:>2}", x)).join(" "))
+                .join("\n")
+        })
+        .join("\n\n")
+}
+
 fn has_bingo(checked: ArrayView2<bool>) -> bool {
     (0..N).any(|i| (0..N).all(|j| checked[[i, j]]))
         || (0..N).any(|i| (0..N).all(|j| checked[[j, i]]))
 }
 
-fn play_card(numbers: &[Num], card: &BingoCard) -> Option<(usize, Num)> {
+fn play_card(numbers: &[Num], card: &BingoCard) -> Option<(usize, i64)> {
     let mut checked: Array2<bool> = Array2::from_elem((N, N), false);
 
     for (turn, &x) in enumerate(numbers) {
@@ -51,7 +72,7 @@ fn play_card(numbers: &[Num], card: &BingoCard) -> Option<(usize, Num)> {
         if has_bingo(checked.view()) {
             let score = zip(&checked, card)
                 .filter(|(&c, _)| !c)
-                .map(|(_, y)| y)
+                .map(|(_, &y)| y as i64)
                 .sum();
 
             return Some((turn, score));
@@ -61,35 +82,37 @@ fn play_card(numbers: &[Num], card: &BingoCard) -> Option<(usize, Num)> {
     None
 }
 
-fn play_cards_winner(numbers: &[Num], cards: &[BingoCard]) -> Num {
+fn play_cards_winner(numbers: &[Num], cards: &[BingoCard]) -> Result<i64> {
     let (turn, score) = cards
         .iter()
         .filter_map(|card| play_card(numbers, card))
         .min_by_key(|&(turn, _)| turn)
-        .unwrap();
+        .context("no card wins")?;
 
-    score * numbers[turn]
+    score
+        .checked_mul(numbers[turn] as i64)
+        .context("score overflows")
 }
 
-fn play_cards_loser(numbers: &[Num], cards: &[BingoCard]) -> Num {
+fn play_cards_loser(numbers: &[Num], cards: &[BingoCard]) -> Result<i64> {
     let (turn, score) = cards
         .iter()
         .filter_map(|card| play_card(numbers, card))
         .max_by_key(|&(turn, _)| turn)
-        .unwrap();
+        .context("no card wins")?;
 
-    score * numbers[turn]
+    score
+        .checked_mul(numbers[turn] as i64)
+        .context("score overflows")
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
+    ensure!(lines.len() > 2, "invalid input");
     let numbers = parse_numbers(lines[0])?;
     let cards = parse_cards(&lines[2..])?;
 
-    let answer = play_cards_winner(&numbers, &cards);
-    println!("part A: {:?}", answer);
-
-    let answer = play_cards_loser(&numbers, &cards);
-    println!("part B: {:?}", answer);
+    answer('A', play_cards_winner(&numbers, &cards)?);
+    answer('B', play_cards_loser(&numbers, &cards)?);
 
     Ok(())
 }
@@ -127,7 +150,7 @@ mod tests {
     fn test_a() {
         let (numbers, cards) = input();
 
-        let answer = play_cards_winner(&numbers, &cards);
+        let answer = play_cards_winner(&numbers, &cards).unwrap();
         assert_eq!(answer, 4512);
     }
 
@@ -135,7 +158,48 @@ mod tests {
     fn test_b() {
         let (numbers, cards) = input();
 
-        let answer = play_cards_loser(&numbers, &cards);
+        let answer = play_cards_loser(&numbers, &cards).unwrap();
         assert_eq!(answer, 1924);
     }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let numbers = (0..rng.random_range(1..30))
+                .map(|_| rng.random_range(0..100))
+                .collect_vec();
+            let line = format_numbers(&numbers);
+            assert_eq!(parse_numbers(&line).unwrap(), numbers);
+
+            let cards = (0..rng.random_range(1..5))
+                .map(|_| Array2::from_shape_simple_fn((N, N), || rng.random_range(0..100)))
+                .collect_vec();
+            let text = format_cards(&cards);
+            assert_eq!(parse_cards(&text.lines().collect_vec()).unwrap(), cards);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1",
+                "",
+                "22 13 17 11  0",
+                " 8  2 23  4 24",
+                "21  9 14 16  7",
+                " 6 10  3 18  5",
+                " 1 12 20 15 19",
+                "",
+                " 3 15  0  2 22",
+                " 9 18 13 17  5",
+                "19  8  7 25 23",
+                "20 11 10 24  4",
+                "14 21 16 12  6",
+            ],
+            run,
+        );
+    }
 }
@@ -1,54 +1,106 @@
 use crate::common::*;
-use recap::Recap;
-use serde::Deserialize;
+use std::fmt::{self, Display};
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize, PartialEq, Recap)]
-#[recap(regex = r#"(?P<x0>\d+),(?P<y0>\d+) -> (?P<x1>\d+),(?P<y1>\d+)"#)]
+#[derive(Debug, PartialEq)]
 struct Segment {
-    x0: i32,
-    y0: i32,
-    x1: i32,
-    y1: i32,
+    start: Point2,
+    end: Point2,
+}
+
+impl Segment {
+    fn new(x0: i64, y0: i64, x1: i64, y1: i64) -> Self {
+        Self {
+            start: Point2::new(x0, y0),
+            end: Point2::new(x1, y1),
+        }
+    }
+}
+
+impl FromStr for Segment {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (x0, y0, x1, y1): (i32, i32, i32, i32) = scan!("{i32},{i32} -> {i32},{i32}", line)?;
+        Ok(Segment::new(x0.into(), y0.into(), x1.into(), y1.into()))
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.start, self.end)
+    }
 }
 
 fn parse(lines: Lines) -> Result<Vec<Segment>> {
     lines
         .iter()
         .filter(|s| !s.is_empty())
-        .map(|s| Ok(s.parse()?))
+        .map(|s| s.parse())
         .collect()
 }
 
 fn overlaps(segments: &[Segment], diagonals: bool) -> usize {
-    let mut points: HashMap<(i32, i32), usize> = default();
+    let mut points = Counter::new();
 
     for seg in segments {
-        let nx = (seg.x1 - seg.x0).abs();
-        let dx = (seg.x1 - seg.x0).signum();
-        let ny = (seg.y1 - seg.y0).abs();
-        let dy = (seg.y1 - seg.y0).signum();
+        let delta = seg.end - seg.start;
+        let (nx, ny) = (delta.x.abs(), delta.y.abs());
 
         if !(nx == 0 || ny == 0 || (nx == ny && diagonals)) {
             continue;
         }
 
-        let n = i32::max(nx, ny);
+        for i in 0..=delta.norm_max() {
+            points.add(seg.start + delta.signum() * i);
+        }
+    }
+
+    points.iter().filter(|&(_, n)| n > 1).count()
+}
+
+/// Counts the same points as `overlaps(segments, false)`, one row at a time. A horizontal segment
+/// covers an interval of its row and a vertical segment a single point of every row it crosses.
+fn straight_overlaps(segments: &[Segment]) -> usize {
+    let mut rows: HashMap<i64, Vec<Interval>> = HashMap::default();
 
-        for i in 0..=n {
-            let x = seg.x0 + dx * i;
-            let y = seg.y0 + dy * i;
-            *points.entry((x, y)).or_default() += 1;
+    for seg in segments {
+        let (x0, x1) = (seg.start.x.min(seg.end.x), seg.start.x.max(seg.end.x));
+        let (y0, y1) = (seg.start.y.min(seg.end.y), seg.start.y.max(seg.end.y));
+
+        if y0 == y1 {
+            rows.entry(y0)
+                .or_default()
+                .push(Interval::inclusive(x0, x1));
+        } else if x0 == x1 {
+            for y in y0..=y1 {
+                rows.entry(y).or_default().push(Interval::inclusive(x0, x0));
+            }
         }
     }
 
-    points.values().filter(|&&s| s > 1).count()
+    let overlaps = rows.values().map(|intervals| {
+        let mut covered = IntervalSet::new();
+        let mut twice = IntervalSet::new();
+
+        for &interval in intervals {
+            for overlap in covered.overlap(interval).collect_vec() {
+                twice.insert(overlap);
+            }
+            covered.insert(interval);
+        }
+
+        twice.len()
+    });
+
+    overlaps.sum::<i64>() as usize
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let lines = parse(lines)?;
 
-    println!("part A: {:?}", overlaps(&lines, false));
-    println!("part B: {:?}", overlaps(&lines, true));
+    answer('A', straight_overlaps(&lines));
+    answer('B', overlaps(&lines, true));
 
     Ok(())
 }
@@ -74,13 +126,83 @@ mod tests {
         parse(&lines).unwrap()
     }
 
+    /// Counts the points of straight segments that overlap by intersecting every pair of them.
+    fn pairwise_overlaps(segments: &[Segment]) -> usize {
+        let regions = segments
+            .iter()
+            .filter(|seg| seg.start.x == seg.end.x || seg.start.y == seg.end.y)
+            .map(|seg| Point2::bounding_box([seg.start, seg.end]).unwrap())
+            .collect_vec();
+        let common = regions
+            .iter()
+            .tuple_combinations()
+            .filter_map(|(a, b)| a.intersection(b))
+            .collect_vec();
+
+        union_volume(&common) as usize
+    }
+
     #[test]
     fn test_a() {
         assert_eq!(overlaps(&input(), false), 5);
+        assert_eq!(straight_overlaps(&input()), 5);
+        assert_eq!(pairwise_overlaps(&input()), 5);
+
+        let mut rng = test_rng();
+        for _ in 0..100 {
+            let segments = (0..rng.random_range(1..20))
+                .map(|_| {
+                    let (x0, y0) = (rng.random_range(0..20), rng.random_range(0..20));
+                    let (x1, y1) = match rng.random_range(0..3) {
+                        0 => (x0, rng.random_range(0..20)),
+                        1 => (rng.random_range(0..20), y0),
+                        _ => (rng.random_range(0..20), rng.random_range(0..20)),
+                    };
+
+                    Segment::new(x0, y0, x1, y1)
+                })
+                .collect_vec();
+
+            let expected = overlaps(&segments, false);
+            assert_eq!(straight_overlaps(&segments), expected);
+            assert_eq!(pairwise_overlaps(&segments), expected);
+        }
     }
 
     #[test]
     fn test_b() {
         assert_eq!(overlaps(&input(), true), 12);
     }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let mut coord = || rng.random_range(0..1000);
+            let segment = Segment::new(coord(), coord(), coord(), coord());
+            let line = segment.to_string();
+
+            assert_eq!(parse(&[&line]).unwrap(), [segment]);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "0,9 -> 5,9",
+                "8,0 -> 0,8",
+                "9,4 -> 3,4",
+                "2,2 -> 2,1",
+                "7,0 -> 7,4",
+                "6,4 -> 2,0",
+                "0,9 -> 2,9",
+                "3,4 -> 1,4",
+                "0,0 -> 8,8",
+                "5,5 -> 8,2",
+            ],
+            run,
+        );
+    }
 }
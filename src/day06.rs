@@ -1,46 +1,74 @@
 use crate::common::*;
+use std::fmt::{self, Display};
 
 const N: usize = 9;
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
 struct Population {
     counts: Box<[u128; N]>,
     offset: usize,
 }
 
+impl Display for Population {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let timers = (0..N).flat_map(|timer| {
+            let count = self.counts[(self.offset + timer) % N];
+            (0..count).map(move |_| timer)
+        });
+
+        write!(f, "{}", timers.format(","))
+    }
+}
+
 fn parse_population(line: &str) -> Result<Population> {
     let mut pop = Population::default();
 
     for n in parse_list::<usize>(line, ',')? {
+        ensure!(n < N, "invalid timer: {}", n);
         pop.counts[n] += 1;
     }
 
     Ok(pop)
 }
 
-fn simulate_day(mut fish: Population) -> Population {
-    let offset = fish.offset;
-    fish.counts[(offset + N - 2) % N] += fish.counts[offset];
-    fish.offset = (offset + 1) % N;
-    fish
+/// Linear map that advances the counts per timer value by one day.
+fn transition() -> Matrix {
+    Matrix::from_fn(N, |to, from| match from {
+        0 => (to == 6 || to == 8) as u128,
+        _ => (to + 1 == from) as u128,
+    })
 }
 
-fn population_after_days(mut fish: Population, days: usize) -> u128 {
-    for _ in 0..days {
-        fish = simulate_day(fish);
-    }
+fn timer_counts(fish: &Population) -> Vec<u128> {
+    (0..N).map(|t| fish.counts[(fish.offset + t) % N]).collect()
+}
+
+fn population_after(fish: &Population, days: u64) -> Result<Population> {
+    let counts = transition().pow(days)?.apply(&timer_counts(fish))?;
+
+    Ok(Population {
+        counts: Box::new(counts.try_into().unwrap()),
+        offset: 0,
+    })
+}
+
+fn population_after_days(fish: &Population, days: u64) -> Result<u128> {
+    let fish = population_after(fish, days)?;
 
-    fish.counts.iter().sum()
+    fish.counts
+        .iter()
+        .try_fold(0u128, |sum, &n| sum.checked_add(n))
+        .context("population overflows")
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let initial = parse_population(lines[0])?;
 
-    let total = population_after_days(initial.clone(), 80);
-    println!("part A: {:?}", total);
+    let total = population_after_days(&initial, 80)?;
+    answer('A', total);
 
-    let total = population_after_days(initial, 256);
-    println!("part B: {:?}", total);
+    let total = population_after_days(&initial, 256)?;
+    answer('B', total);
 
     Ok(())
 }
@@ -55,11 +83,50 @@ mod tests {
 
     #[test]
     fn test_a() {
-        assert_eq!(population_after_days(input(), 80), 5934);
+        assert_eq!(population_after_days(&input(), 80).unwrap(), 5934);
     }
 
     #[test]
     fn test_b() {
-        assert_eq!(population_after_days(input(), 256), 26984457539);
+        assert_eq!(population_after_days(&input(), 256).unwrap(), 26984457539);
+    }
+
+    #[test]
+    fn test_large() {
+        assert!(population_after_days(&input(), 1_000_000_000_000_000).is_err());
+
+        let modulus = 1_000_000_007;
+        let counts = timer_counts(&input());
+        let after = |days| {
+            let matrix = transition().with_modulus(modulus).pow(days).unwrap();
+            matrix.apply(&counts).unwrap().iter().sum::<u128>() % modulus as u128
+        };
+
+        assert_eq!(after(256), 26984457539 % modulus as u128);
+        assert!(after(1_000_000_000_000_000) < modulus as u128);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(input().to_string(), "1,2,3,3,4");
+        let next = population_after(&input(), 1).unwrap();
+        assert_eq!(next.to_string(), "0,1,2,2,3");
+
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let mut pop = Population::default();
+            for _ in 0..rng.random_range(1..50) {
+                pop.counts[rng.random_range(0..N)] += 1;
+            }
+
+            let line = pop.to_string();
+            assert_eq!(parse_population(&line).unwrap(), pop);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(&["3,4,3,1,2"], run);
     }
 }
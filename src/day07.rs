@@ -1,26 +1,61 @@
 use crate::common::*;
 
-fn solve<F: Fn(i32) -> i32>(pos: &[i32], fuel: F) -> i32 {
-    let (&min, &max) = pos.iter().minmax().into_option().unwrap();
-    (min..=max)
-        .map(|p| pos.iter().map(|x| fuel((p - x).abs())).sum())
-        .min()
-        .unwrap()
+/// Number of ranges the candidate positions are split into, enough to keep all cores busy.
+const RANGES: i64 = 64;
+
+fn solve<F: Fn(i64) -> Option<i64> + Sync>(pos: &[i32], fuel: F) -> Result<i64> {
+    let (&min, &max) = pos
+        .iter()
+        .minmax()
+        .into_option()
+        .context("no crabs found")?;
+
+    let total = |p: i64| {
+        pos.iter()
+            .try_fold(0i64, |acc, &x| acc.checked_add(fuel((p - x as i64).abs())?))
+    };
+
+    // The candidates are only visited range by range, so they are never all kept in memory.
+    let (min, max) = (min as i64, max as i64);
+    let size = (max - min + RANGES) / RANGES;
+    let ranges = (min..=max)
+        .step_by(size as usize)
+        .map(|lo| (lo, max.min(lo + size - 1)));
+
+    let minima = par_map(&ranges.collect_vec(), |&(lo, hi)| {
+        (lo..=hi).try_fold(i64::MAX, |best, p| Some(best.min(total(p)?)))
+    });
+    let mut best = i64::MAX;
+
+    for minimum in minima {
+        best = best.min(minimum.context("fuel overflows")?);
+    }
+
+    Ok(best)
 }
 
-fn solve_a(pos: &[i32]) -> i32 {
-    solve(pos, |dist| dist)
+fn solve_a(pos: &[i32]) -> Result<i64> {
+    solve(pos, Some)
 }
 
-fn solve_b(pos: &[i32]) -> i32 {
-    solve(pos, |dist| dist * (dist + 1) / 2)
+fn solve_b(pos: &[i32]) -> Result<i64> {
+    solve(pos, |dist| Some(dist.checked_mul(dist + 1)? / 2))
+}
+
+fn parse(line: &str) -> Result<Vec<i32>> {
+    parse_list(line, ',')
+}
+
+#[allow(dead_code)]
+fn format(pos: &[i32]) -> String {
+    pos.iter().join(",")
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
-    let numbers = parse_list(lines[0], ',')?;
+    let numbers = parse(lines[0])?;
 
-    println!("part A: {}", solve_a(&numbers));
-    println!("part B: {}", solve_b(&numbers));
+    answer('A', solve_a(&numbers)?);
+    answer('B', solve_b(&numbers)?);
 
     Ok(())
 }
@@ -35,11 +70,29 @@ mod tests {
 
     #[test]
     fn test_a() {
-        assert_eq!(solve_a(&input()), 37)
+        assert_eq!(solve_a(&input()).unwrap(), 37)
     }
 
     #[test]
     fn test_b() {
-        assert_eq!(solve_b(&input()), 168)
+        assert_eq!(solve_b(&input()).unwrap(), 168)
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let pos = (0..rng.random_range(1..50))
+                .map(|_| rng.random_range(0..2000))
+                .collect_vec();
+
+            assert_eq!(parse(&format(&pos)).unwrap(), pos);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(&["16,1,2,0,4,2,7,1,2,14"], run);
     }
 }
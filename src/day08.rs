@@ -1,4 +1,5 @@
 use crate::common::*;
+use std::fmt::{self, Display};
 
 lazy_static::lazy_static! {
     static ref DIGITS: [Sample; 10] = {
@@ -13,11 +14,30 @@ lazy_static::lazy_static! {
 
 type Sample = [bool; 7];
 
+#[derive(Debug, PartialEq, Eq)]
 struct Entry {
     inputs: [Sample; 10],
     outputs: [Sample; 4],
 }
 
+fn format_sample(sample: &Sample) -> String {
+    enumerate(sample)
+        .filter(|(_, &active)| active)
+        .map(|(i, _)| (b'a' + i as u8) as char)
+        .collect()
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} | {}",
+            self.inputs.iter().map(format_sample).join(" "),
+            self.outputs.iter().map(format_sample).join(" "),
+        )
+    }
+}
+
 fn parse_sample(chars: &str) -> Result<Sample> {
     let mut sample: Sample = default();
 
@@ -79,7 +99,7 @@ fn solve_a(entries: &[Entry]) -> usize {
 type Mapping = [usize; 7];
 
 #[allow(clippy::needless_range_loop)]
-fn find_mapping(entry: &Entry) -> Mapping {
+fn find_mapping(entry: &Entry) -> Result<Mapping> {
     let mut table = [[true; 7]; 7];
 
     for src in 0..7 {
@@ -132,13 +152,13 @@ fn find_mapping(entry: &Entry) -> Mapping {
     }
 
     if found != 7 {
-        panic!("invalid input");
+        bail!("no consistent wire mapping for entry: {}", entry);
     }
 
-    mapping
+    Ok(mapping)
 }
 
-fn decode_digit(encoded: Sample, mapping: Mapping) -> usize {
+fn decode_digit(encoded: Sample, mapping: Mapping) -> Result<usize> {
     let mut decoded = Sample::default();
 
     for (i, &active) in enumerate(&encoded) {
@@ -149,39 +169,39 @@ fn decode_digit(encoded: Sample, mapping: Mapping) -> usize {
 
     for (i, &digit) in enumerate(&*DIGITS) {
         if digit == decoded {
-            return i;
+            return Ok(i);
         }
     }
 
-    panic!("unknown digit: {:?}", decoded);
+    bail!("unknown digit: {}", format_sample(&decoded));
 }
 
-fn decode_output(entry: &Entry, mapping: Mapping) -> usize {
+fn decode_output(entry: &Entry, mapping: Mapping) -> Result<usize> {
     let mut result = 0;
 
     for output in entry.outputs {
-        result = result * 10 + decode_digit(output, mapping);
+        result = result * 10 + decode_digit(output, mapping)?;
     }
 
-    result
+    Ok(result)
 }
 
-fn solve_b(entries: &[Entry]) -> usize {
+fn solve_b(entries: &[Entry]) -> Result<usize> {
     let mut sum = 0;
 
     for entry in entries {
-        let mapping = find_mapping(entry);
-        sum += decode_output(entry, mapping);
+        let mapping = find_mapping(entry)?;
+        sum += decode_output(entry, mapping)?;
     }
 
-    sum
+    Ok(sum)
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let entries = parse(lines)?;
 
-    println!("part A: {:?}", solve_a(&entries));
-    println!("part B: {:?}", solve_b(&entries));
+    answer('A', solve_a(&entries));
+    answer('B', solve_b(&entries)?);
 
     Ok(())
 }
@@ -214,6 +234,36 @@ mod tests {
 
     #[test]
     fn test_b() {
-        assert_eq!(solve_b(&input()), 61229);
+        assert_eq!(solve_b(&input()).unwrap(), 61229);
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+        let mut sample = || -> Sample {
+            let mut sample: Sample = default();
+            sample[rng.random_range(0..7)] = true;
+            sample.iter_mut().for_each(|s| *s |= rng.random::<bool>());
+            sample
+        };
+
+        for _ in 0..100 {
+            let entry = Entry {
+                inputs: [(); 10].map(|_| sample()),
+                outputs: [(); 4].map(|_| sample()),
+            };
+            let line = entry.to_string();
+
+            assert_eq!(parse(&[&line]).unwrap(), [entry]);
+        }
+
+        for entry in input() {
+            assert_eq!(parse_entry(&entry.to_string()).unwrap(), entry);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(&["be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe", "edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc"], run);
     }
 }
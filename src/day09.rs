@@ -1,89 +1,87 @@
 use crate::common::*;
-use ndarray::{Array2, ArrayView2};
 
 type Num = i32;
 
-fn neighbors(map: ArrayView2<Num>, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
-    let (n, m) = map.dim();
-
-    [[0, 1], [0, -1], [1, 0], [-1, 0]]
-        .iter()
-        .map(move |&[dx, dy]| (x as isize + dx, y as isize + dy))
-        .filter_map(|(x, y)| Some((x.try_into().ok()?, y.try_into().ok()?)))
-        .filter(move |&(x, y)| x < n && y < m)
+fn parse(lines: Lines) -> Result<Grid<Num>> {
+    Grid::from_lines(lines, |c| {
+        Ok(c.to_digit(10).context("invalid digit")? as Num)
+    })
 }
 
-fn parse(lines: Lines) -> Result<Array2<Num>> {
-    let n = lines.len();
-    let m = lines[0].len();
-    let mut grid = Array2::from_elem((n, m), 0);
-
-    for (i, line) in enumerate(lines) {
-        for (j, c) in enumerate(line.chars()) {
-            grid[[i, j]] = c.to_string().parse()?;
-        }
-    }
-
-    Ok(grid)
+#[allow(dead_code)]
+fn format(map: &Grid<Num>) -> String {
+    map.to_string()
 }
 
-fn solve_a(map: ArrayView2<Num>) -> Num {
+fn solve_a(map: &Grid<Num>) -> Num {
     map.indexed_iter()
-        .map(|((i, j), &value)| {
-            let is_low = all(neighbors(map.view(), i, j), |neighbor| {
-                map[neighbor] > value
-            });
+        .map(|(pos, &value)| {
+            let is_low = all(map.neighbors4(pos), |neighbor| map[neighbor] > value);
 
             is_low as Num * (1 + value)
         })
         .sum()
 }
 
-fn solve_b(map: ArrayView2<Num>) -> usize {
-    let mut next_label = 0;
-    let mut labels = Array2::<Option<Num>>::from_elem(map.dim(), None);
+fn index(map: &Grid<Num>, [i, j]: Pos) -> usize {
+    i * map.ncols() + j
+}
 
-    for ((i, j), &value) in map.indexed_iter() {
-        if value != 9 {
-            labels[[i, j]] = Some(next_label);
-            next_label += 1;
-        }
-    }
+/// Joins every cell with its neighbors, cells of height 9 do not belong to any basin.
+fn basin_sets(map: &Grid<Num>) -> UnionFind {
+    let mut sets = UnionFind::new(map.len());
 
-    loop {
-        let mut changes = 0;
-
-        for ((i, j), _) in map.indexed_iter() {
-            for neighbor in neighbors(map.view(), i, j) {
-                if let Some(my_label) = labels[[i, j]] {
-                    if let Some(his_label) = labels[neighbor] {
-                        if my_label > his_label {
-                            labels[[i, j]] = Some(his_label);
-                            changes += 1;
-                        }
-                    }
-                }
+    for (pos, &value) in map.indexed_iter() {
+        for neighbor in [[pos[0] + 1, pos[1]], [pos[0], pos[1] + 1]] {
+            if value != 9 && map.get(neighbor).is_some_and(|&v| v != 9) {
+                sets.union(index(map, pos), index(map, neighbor));
             }
         }
-
-        if changes == 0 {
-            break;
-        }
     }
 
-    let mut counts = HashMap::default();
-    for label in flatten(labels) {
-        *counts.entry(label).or_default() += 1;
+    sets
+}
+
+/// Labels every cell with its basin, cells of height 9 do not belong to any basin.
+fn basins(map: &Grid<Num>) -> Grid<Option<usize>> {
+    let mut sets = basin_sets(map);
+
+    Grid::from_fn(map.nrows(), map.ncols(), |pos| {
+        (map[pos] != 9).then(|| sets.find(index(map, pos)))
+    })
+}
+
+fn solve_b(map: &Grid<Num>) -> usize {
+    let sizes: Counter<usize> = basins(map).iter().flatten().copied().collect();
+
+    sizes.most_common(3).iter().map(|&(_, n)| n).product()
+}
+
+/// Renders the basins in distinct colors, lower points are brighter.
+fn render(map: &Grid<Num>) -> Result {
+    if !is_rendering() {
+        return Ok(());
     }
 
-    counts.values().sorted().rev().take(3).product()
+    let labels = basins(map);
+
+    let image = Image::from_fn(map.ncols(), map.nrows(), |x, y| match labels[[y, x]] {
+        Some(label) => {
+            let height = map[[y, x]] as u32;
+            label_color(label).map(|c| (c as u32 * (12 - height) / 12) as u8)
+        }
+        None => BLACK,
+    });
+
+    save_image("day09", &image)
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let map = parse(lines)?;
 
-    println!("part A: {:?}", solve_a(map.view()));
-    println!("part B: {:?}", solve_b(map.view()));
+    answer('A', solve_a(&map));
+    answer('B', solve_b(&map));
+    render(&map)?;
 
     Ok(())
 }
@@ -92,7 +90,7 @@ pub(crate) fn run(lines: Lines) -> Result {
 mod tests {
     use super::*;
 
-    fn input() -> Array2<Num> {
+    fn input() -> Grid<Num> {
         let lines = [
             "2199943210",
             "3987894921",
@@ -106,11 +104,38 @@ mod tests {
 
     #[test]
     fn test_a() {
-        assert_eq!(solve_a(input().view()), 15);
+        assert_eq!(solve_a(&input()), 15);
     }
 
     #[test]
     fn test_b() {
-        assert_eq!(solve_b(input().view()), 1134);
+        assert_eq!(solve_b(&input()), 1134);
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let (n, m) = (rng.random_range(1..20), rng.random_range(1..20));
+            let map = Grid::from_fn(n, m, |_| rng.random_range(0..=9));
+            let text = format(&map);
+
+            assert_eq!(parse(&text.lines().collect_vec()).unwrap(), map);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "2199943210",
+                "3987894921",
+                "9856789892",
+                "8767896789",
+                "9899965678",
+            ],
+            run,
+        );
     }
 }
@@ -44,30 +44,35 @@ fn solve_b(lines: Lines) -> Result<usize> {
 
     for line in lines {
         if let Ok(stack) = parse(line) {
-            let mut score = 0;
+            let mut score: usize = 0;
 
             for c in rev(stack) {
-                score = score * 5
-                    + match c {
-                        ')' => 1,
-                        ']' => 2,
-                        '}' => 3,
-                        '>' => 4,
-                        _ => panic!("invalid character: {:?}", c),
-                    }
+                let points = match c {
+                    ')' => 1,
+                    ']' => 2,
+                    '}' => 3,
+                    '>' => 4,
+                    _ => bail!("invalid character: {:?}", c),
+                };
+
+                score = score
+                    .checked_mul(5)
+                    .and_then(|s| s.checked_add(points))
+                    .context("score overflows")?;
             }
 
             scores.push(score);
         }
     }
 
+    ensure!(!scores.is_empty(), "no incomplete lines found");
     scores.sort_unstable();
     Ok(scores[scores.len() / 2])
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
-    println!("part A: {:?}", solve_a(lines)?);
-    println!("part B: {:?}", solve_b(lines)?);
+    answer('A', solve_a(lines)?);
+    answer('B', solve_b(lines)?);
 
     Ok(())
 }
@@ -100,4 +105,19 @@ mod tests {
     fn test_b() {
         assert_eq!(solve_b(input()).unwrap(), 288957);
     }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "[({(<(())[]>[[{[]{<()<>>",
+                "[(()[<>])]({[<{<<[]>>(",
+                "{([(<{}[<>[]}>{[]{[(<()>",
+                "(((({<>}<{<{<>}{[]{[]{}",
+                "[[<[([]))<([[{}[[()]]]",
+                "[{[{({}]{}}([{[{{{}}([]",
+            ],
+            run,
+        );
+    }
 }
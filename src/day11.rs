@@ -1,114 +1,103 @@
 use crate::common::*;
-use ndarray::Array2;
-use std::collections::HashSet;
 
-const N: usize = 10;
-
-fn parse(lines: Lines) -> Result<Array2<i32>> {
-    ensure!(lines.len() == N, "invalid input");
-    let mut grid = Array2::zeros((N, N));
-
-    for (i, line) in enumerate(lines) {
-        for (j, c) in enumerate(line.chars()) {
-            let x = c.to_string().parse()?;
-            ensure!((0..9).contains(&x) && j < N, "invalid character: {:?}", x);
-
-            grid[[i, j]] = x;
-        }
-    }
-
-    Ok(grid)
+fn parse(lines: Lines) -> Result<Grid<i32>> {
+    Grid::from_lines(lines, |c| match c.to_digit(10) {
+        Some(x) => Ok(x as i32),
+        None => bail!("invalid character: {:?}", c),
+    })
 }
 
-fn neighbors(i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> {
-    [
-        [-1, 1],
-        [-1, 0],
-        [-1, -1],
-        [0, 1],
-        [0, -1],
-        [1, 1],
-        [1, 0],
-        [1, -1],
-    ]
-    .iter()
-    .map(move |&[di, dj]| (i as isize + di, j as isize + dj))
-    .filter_map(|(x, y)| Some((x.try_into().ok()?, y.try_into().ok()?)))
-    .filter(move |&(x, y)| x < N && y < N)
+#[allow(dead_code)]
+fn format(grid: &Grid<i32>) -> String {
+    grid.to_string()
 }
 
-fn step(grid: &mut Array2<i32>) -> usize {
-    assert_eq!(grid.shape(), &[N, N]);
-    let mut active = Vec::new();
-    let mut visited = HashSet::new();
+/// Energy level of an octopus that already flashed during the current step.
+const FLASHED: i32 = -1;
+
+fn step(grid: &mut Grid<i32>) -> usize {
+    let mut octopuses = Automaton::new(grid.clone(), Boundary::Fixed);
 
     // First, the energy level of each octopus increases by 1.
-    for i in 0..N {
-        for j in 0..N {
-            grid[[i, j]] += 1;
-
-            // any octopus with an energy level greater than 9 flashes.
-            if grid[[i, j]] > 9 {
-                active.push((i, j));
-                visited.insert((i, j));
-            }
-        }
-    }
+    octopuses.step(|cell| cell.center() + 1);
+
+    // Any octopus with an energy level greater than 9 flashes. This increases the energy level of
+    // all adjacent octopuses by 1, including octopuses that are diagonally adjacent. If this
+    // causes an octopus to have an energy level greater than 9, it also flashes.
+    octopuses.step_until_stable(|cell| match *cell.center() {
+        energy if energy == FLASHED || energy > 9 => FLASHED,
+        energy => energy + cell.neighbors8().filter(|&&e| e > 9).count() as i32,
+    });
+
+    // Finally, any octopus that flashed during this step has its energy level set to 0.
+    *grid = octopuses
+        .into_grid()
+        .map(|&energy| if energy == FLASHED { 0 } else { energy });
+    flashed(grid)
+}
 
-    while let Some((i, j)) = active.pop() {
-        for neighbor in neighbors(i, j) {
-            if visited.contains(&neighbor) {
-                continue;
-            }
-
-            // increases the energy level of all adjacent octopuses by 1,
-            // including octopuses that are diagonally adjacent
-            grid[neighbor] += 1;
-
-            // If this causes an octopus to have an energy level greater than 9, it also flashes.
-            if grid[neighbor] > 9 {
-                active.push(neighbor);
-                visited.insert(neighbor);
-            }
-        }
-    }
+/// Simulates the octopuses until their energy levels repeat.
+fn simulate(input: &Grid<i32>) -> Cycle<Grid<i32>> {
+    find_cycle(input.clone(), |grid| {
+        let mut grid = grid.clone();
+        step(&mut grid);
+        grid
+    })
+}
 
-    for &index in &visited {
-        grid[index] = 0;
-    }
+/// Every octopus that flashed during a step ends with an energy level of 0.
+fn flashed(grid: &Grid<i32>) -> usize {
+    grid.iter().filter(|&&energy| energy == 0).count()
+}
 
-    visited.len()
+fn count_flashes(input: &Grid<i32>, steps: usize) -> u64 {
+    simulate(input).sum_over(steps, |grid| flashed(grid) as u64)
 }
 
-fn count_flashes(input: &Array2<i32>, steps: usize) -> usize {
-    let mut total = 0;
-    let mut grid = input.clone();
+fn first_simulate_flash(input: &Grid<i32>) -> Result<usize> {
+    let cycle = simulate(input);
 
-    for _ in 0..steps {
-        total += step(&mut grid);
+    // Once every state in the cycle has been seen, the octopuses will never synchronize.
+    match find(1..=cycle.states().len(), |&steps| {
+        flashed(cycle.nth(steps)) == input.len()
+    }) {
+        Some(steps) => Ok(steps),
+        None => bail!("octopuses never flash simultaneously"),
     }
+}
 
-    total
+fn to_image(grid: &Grid<i32>) -> Image {
+    Image::from_fn(grid.ncols(), grid.nrows(), |x, y| match grid[[y, x]] {
+        0 => WHITE,
+        energy => shade(energy as usize, 2 * 9),
+    })
 }
 
-fn first_simulate_flash(input: &Array2<i32>) -> usize {
-    let mut steps = 0;
+/// Renders every step until the octopuses flash simultaneously, flashing octopuses are white.
+fn render(input: &Grid<i32>, steps: usize) -> Result {
+    let mut frames = Frames::new("day11")?;
     let mut grid = input.clone();
 
-    loop {
-        steps += 1;
+    if frames.is_enabled() {
+        frames.push(&to_image(&grid))?;
 
-        if step(&mut grid) == N * N {
-            return steps;
+        for _ in 0..steps {
+            step(&mut grid);
+            frames.push(&to_image(&grid))?;
         }
     }
+
+    Ok(())
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let grid = parse(lines)?;
 
-    println!("part A: {:?}", count_flashes(&grid, 100));
-    println!("part B: {:?}", first_simulate_flash(&grid));
+    answer('A', count_flashes(&grid, 100));
+
+    let steps = first_simulate_flash(&grid)?;
+    answer('B', steps);
+    render(&grid, steps)?;
 
     Ok(())
 }
@@ -117,7 +106,7 @@ pub(crate) fn run(lines: Lines) -> Result {
 mod tests {
     use super::*;
 
-    fn input() -> Array2<i32> {
+    fn input() -> Grid<i32> {
         let lines = [
             "5483143223",
             "2745854711",
@@ -139,10 +128,49 @@ mod tests {
         assert_eq!(count_flashes(&input(), 1), 0);
         assert_eq!(count_flashes(&input(), 2), 35);
         assert_eq!(count_flashes(&input(), 100), 1656);
+
+        // All octopuses flash every 10 steps once they are synchronized.
+        let flashes = count_flashes(&input(), 1000);
+        assert_eq!(
+            count_flashes(&input(), 1_000_000_000_000),
+            flashes + (1_000_000_000_000 - 1000) / 10 * 100
+        );
     }
 
     #[test]
     fn test_b() {
-        assert_eq!(first_simulate_flash(&input()), 195);
+        assert_eq!(first_simulate_flash(&input()).unwrap(), 195);
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let (n, m) = (rng.random_range(1..20), rng.random_range(1..20));
+            let grid = Grid::from_fn(n, m, |_| rng.random_range(0..=9));
+            let text = format(&grid);
+
+            assert_eq!(parse(&text.lines().collect_vec()).unwrap(), grid);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "5483143223",
+                "2745854711",
+                "5264556173",
+                "6141336146",
+                "6357385478",
+                "4167524645",
+                "2176841721",
+                "6882881134",
+                "4846848554",
+                "5283751526",
+            ],
+            run,
+        );
     }
 }
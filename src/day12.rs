@@ -1,86 +1,93 @@
 use crate::common::*;
 
-type Map = HashMap<String, Vec<String>>;
+/// Caves, with an attribute telling whether the cave is small.
+type Map = Graph<bool>;
 
 fn parse(lines: Lines) -> Result<Map> {
-    let mut output: Map = default();
+    let mut output = Map::new();
 
     for line in lines {
         let (x, y) = line
             .split_once('-')
             .ok_or_else(|| anyhow!("invalid input"))?;
-        output.entry(x.to_string()).or_default().push(y.to_string());
-        output.entry(y.to_string()).or_default().push(x.to_string());
+
+        // Two connected big caves allow infinitely many paths.
+        ensure!(
+            is_small(x) || is_small(y),
+            "big caves are connected: {:?}",
+            line
+        );
+
+        let x = output.add_node(x, is_small);
+        let y = output.add_node(y, is_small);
+        output.connect(x, y);
     }
 
+    ensure!(output.node("start").is_some(), "no start cave found");
+    ensure!(output.len() <= 64, "too many caves: {}", output.len());
     Ok(output)
 }
 
-fn is_small(name: &str) -> bool {
-    name.chars().all(|c| c.is_ascii_lowercase())
-}
+#[allow(dead_code)]
+fn format(map: &Map) -> String {
+    let mut lines = vec![];
 
-fn count_paths(map: &Map, double_allowed: bool) -> usize {
-    let mut stack = vec![];
-    let mut count = 0;
+    for x in map.nodes().sorted_by_key(|&x| map.name(x)) {
+        let neighbors = map.neighbors(x).iter().map(|&y| map.name(y)).sorted();
+        let x = map.name(x);
+
+        // Every edge is stored in both directions, except for self-loops which are stored twice.
+        let forward = neighbors.clone().filter(|&y| x < y);
+        let loops = neighbors.filter(|&y| x == y).step_by(2);
 
-    struct State<'a> {
-        current: &'a str,
-        visited: Vec<&'a str>,
-        visited_twice: bool,
+        for y in forward.chain(loops) {
+            lines.push(format!("{}-{}", x, y));
+        }
     }
 
-    stack.push(State {
-        current: "start",
-        visited: vec![],
-        visited_twice: !double_allowed,
-    });
+    lines.join("\n")
+}
 
-    while let Some(state) = stack.pop() {
-        if state.current == "end" {
-            count += 1;
+fn is_small(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_lowercase())
+}
 
-            if double_allowed {
-                continue;
-            }
+fn count_paths(map: &Map, double_allowed: bool) -> usize {
+    let start = map.node("start").unwrap();
+    let end = match map.node("end") {
+        Some(end) => end,
+        None => return 0,
+    };
+
+    // A bitmask of the small caves visited and whether one was visited twice.
+    let init = (1u64 << start, !double_allowed);
+
+    let paths = map.paths(start, end, init, |&(visited, visited_twice), next| {
+        if next == start {
+            return None;
         }
 
-        for neighbor in &map[state.current] {
-            if neighbor == "start" {
-                continue;
-            }
-
-            let mut visited = state.visited.clone();
-            let mut visited_twice = state.visited_twice;
-
-            if is_small(neighbor) {
-                if visited.contains(&&**neighbor) {
-                    if !visited_twice {
-                        visited_twice = true;
-                    } else {
-                        continue;
-                    }
-                }
-
-                visited.push(neighbor);
-            }
-
-            stack.push(State {
-                current: neighbor,
-                visited,
-                visited_twice,
-            });
+        if !map.attr(next) {
+            return Some((visited, visited_twice));
         }
-    }
 
-    count
+        let bit = 1 << next;
+
+        match (visited & bit != 0, visited_twice) {
+            (false, _) => Some((visited | bit, visited_twice)),
+            (true, false) => Some((visited, true)),
+            (true, true) => None,
+        }
+    });
+
+    paths.count()
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let map = parse(lines)?;
 
-    println!("part A: {:?}", count_paths(&map, false));
-    println!("part A: {:?}", count_paths(&map, true));
+    answer('A', count_paths(&map, false));
+    answer('B', count_paths(&map, true));
 
     Ok(())
 }
@@ -100,11 +107,59 @@ mod tests {
 
     #[test]
     fn test_a() {
-        assert_eq!(count_paths(&input(), false), 19);
+        let map = input();
+        assert_eq!(count_paths(&map, false), 19);
+
+        let [start, end] = ["start", "end"].map(|name| map.node(name).unwrap());
+        let paths = map.all_paths(start, end, |id| !map.attr(id));
+        assert_eq!(paths.count(), 19);
     }
 
     #[test]
     fn test_b() {
         assert_eq!(count_paths(&input(), true), 103);
     }
+
+    #[test]
+    fn test_format() {
+        let normalize = |map: Map| {
+            map.nodes()
+                .flat_map(|x| map.neighbors(x).iter().map(move |&y| (x, y)))
+                .map(|(x, y)| (map.name(x).to_string(), map.name(y).to_string()))
+                .sorted()
+                .collect_vec()
+        };
+
+        let mut rng = test_rng();
+        let names = ["start", "end", "A", "b", "HN", "kj", "dc", "LN"];
+        let small = ["start", "end", "b", "kj", "dc"];
+
+        for _ in 0..100 {
+            let lines = (0..rng.random_range(1..20))
+                .map(|_| {
+                    let x = names[rng.random_range(0..names.len())];
+                    let y = small[rng.random_range(0..small.len())];
+                    format!("{}-{}", x, y)
+                })
+                .chain(["start-A".to_string()])
+                .collect_vec();
+
+            let map = parse(&lines.iter().map(|s| &**s).collect_vec()).unwrap();
+            let text = format(&map);
+            let output = parse(&text.lines().collect_vec()).unwrap();
+
+            assert_eq!(normalize(output), normalize(map));
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "dc-end", "HN-start", "start-kj", "dc-start", "dc-HN", "LN-dc", "HN-end", "kj-sa",
+                "kj-HN", "kj-dc",
+            ],
+            run,
+        );
+    }
 }
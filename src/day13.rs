@@ -1,86 +1,172 @@
 use crate::common::*;
-use ndarray::{s, Array2};
-use recap::Recap;
-use serde::Deserialize;
+use std::fmt::{self, Display};
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize, PartialEq, Recap, Copy, Clone)]
-#[recap(regex = r#"fold along (?P<axis>[xy])=(?P<pos>\d+)"#)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 struct Instruction {
     axis: char,
     pos: usize,
 }
 
-fn parse_grid(lines: Lines) -> Result<Array2<bool>> {
+impl FromStr for Instruction {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (axis, pos) = scan!("fold along {char}={usize}", line)?;
+        ensure!(matches!(axis, 'x' | 'y'), "invalid axis: {:?}", axis);
+
+        Ok(Instruction { axis, pos })
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "fold along {}={}", self.axis, self.pos)
+    }
+}
+
+/// Largest supported paper size, which keeps malformed coordinates from allocating huge grids.
+const MAX_SIZE: usize = 4096;
+
+/// Parses the dots into a grid with a row for every `y` and a column for every `x`, up to the
+/// largest ones that have a dot.
+fn parse_grid(lines: Lines) -> Result<BitGrid> {
     let mut coords = vec![];
-    let mut size = 0;
+    let (mut nrows, mut ncols) = (0, 0);
 
     for line in lines {
         let (x, y) = line
             .split_once(',')
             .ok_or_else(|| anyhow!("invalid line: {:?}", line))?;
-        let (x, y) = (x.parse()?, y.parse()?);
+        let (x, y): (usize, usize) = (x.parse()?, y.parse()?);
+        ensure!(
+            x < MAX_SIZE && y < MAX_SIZE,
+            "coordinate out of range: {:?}",
+            line
+        );
 
-        size = size.max(x + 1).max(y + 1);
+        nrows = nrows.max(y + 1);
+        ncols = ncols.max(x + 1);
         coords.push((x, y));
     }
 
-    let mut grid = Array2::from_elem((size, size), false);
+    let mut grid = BitGrid::new(nrows, ncols);
 
     for (x, y) in coords {
-        grid[[x, y]] = true;
+        grid.set([y, x], true);
     }
 
     Ok(grid)
 }
 
 fn parse_instrs(lines: Lines) -> Result<Vec<Instruction>> {
-    lines.iter().map(|line| Ok(line.parse()?)).collect()
+    lines.iter().map(|line| line.parse()).collect()
 }
 
-fn fold_x(grid: &mut Array2<bool>, fold: usize) {
-    let (mut lhs, mut rhs) = grid.multi_slice_mut((s![..fold, ..], s![fold + 1.., ..]));
-    lhs |= &rhs.slice(s![..fold; -1, ..]);
-    rhs.fill(false);
+#[allow(dead_code)]
+fn format_grid(grid: &BitGrid) -> String {
+    grid.ones().map(|[y, x]| format!("{},{}", x, y)).join("\n")
 }
 
-fn fold_y(grid: &mut Array2<bool>, fold: usize) {
-    let (mut lhs, mut rhs) = grid.multi_slice_mut((s![.., ..fold], s![.., fold + 1..]));
-    lhs |= &rhs.slice(s![.., ..fold;-1]);
-    rhs.fill(false);
+/// Folds the bottom half up onto the top half, the paper keeps its size. There is nothing to fold
+/// below the last dot.
+fn fold_y(grid: &BitGrid, fold: usize) -> BitGrid {
+    let (n, m) = (grid.nrows(), grid.ncols());
+    if fold >= n {
+        return grid.clone();
+    }
+
+    // Row `fold + k` ends up on row `fold - k`.
+    let mirrored = grid
+        .flip_rows()
+        .shift_rows(2 * fold as isize + 1 - n as isize, false);
+    let folded = &grid.resize(fold, m) | &mirrored.resize(fold, m);
+    folded.resize(n, m)
 }
 
-fn fold(grid: &mut Array2<bool>, instrs: &[Instruction]) {
+/// Folds the right half over to the left half, the paper keeps its size. There is nothing to fold
+/// right of the last dot.
+fn fold_x(grid: &BitGrid, fold: usize) -> BitGrid {
+    let (n, m) = (grid.nrows(), grid.ncols());
+    if fold >= m {
+        return grid.clone();
+    }
+
+    let mirrored = grid
+        .flip_columns()
+        .shift_columns(2 * fold as isize + 1 - m as isize, false);
+    let folded = &grid.resize(n, fold) | &mirrored.resize(n, fold);
+    folded.resize(n, m)
+}
+
+fn fold(grid: &mut BitGrid, instrs: &[Instruction]) -> Result {
     for instr in instrs {
-        match instr.axis {
+        *grid = match instr.axis {
             'x' => fold_x(grid, instr.pos),
             'y' => fold_y(grid, instr.pos),
-            other => panic!("invalid axis: {:?}", other),
-        }
+            other => bail!("invalid axis: {:?}", other),
+        };
     }
+
+    Ok(())
 }
 
-fn count_after_one_fold(grid: &Array2<bool>, instr: Instruction) -> usize {
+fn count_after_one_fold(grid: &BitGrid, instr: Instruction) -> Result<usize> {
     let mut grid = grid.clone();
-    fold(&mut grid, &[instr]);
-    sum(map(grid, |x| x as usize))
+    fold(&mut grid, &[instr])?;
+    Ok(grid.count_ones())
+}
+
+/// Renders the part of the paper that contains dots, dots are white.
+fn to_image(grid: &BitGrid) -> Image {
+    let (width, height) = grid
+        .ones()
+        .fold((0, 0), |(w, h), [y, x]| (w.max(x + 1), h.max(y + 1)));
+
+    Image::from_fn(
+        width,
+        height,
+        |x, y| {
+            if grid.get([y, x]) {
+                WHITE
+            } else {
+                BLACK
+            }
+        },
+    )
+}
+
+fn render(grid: &BitGrid, instrs: &[Instruction]) -> Result {
+    let mut frames = Frames::new("day13")?;
+    let mut grid = grid.clone();
+
+    if frames.is_enabled() {
+        frames.push(&to_image(&grid))?;
+
+        for instr in instrs {
+            fold(&mut grid, &[*instr])?;
+            frames.push(&to_image(&grid))?;
+        }
+    }
+
+    Ok(())
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
-    let mid = lines.iter().position(|x| x.is_empty()).unwrap();
+    let mid = lines
+        .iter()
+        .position(|x| x.is_empty())
+        .context("missing empty line between dots and instructions")?;
     let mut grid = parse_grid(&lines[..mid])?;
     let instrs = parse_instrs(&lines[mid + 1..])?;
+    let first = *instrs.first().context("no fold instructions")?;
 
-    println!("part A: {:?}", count_after_one_fold(&grid, instrs[0]));
+    answer('A', count_after_one_fold(&grid, first)?);
 
-    println!("part B:");
-    fold(&mut grid, &instrs);
+    render(&grid, &instrs)?;
+    fold(&mut grid, &instrs)?;
 
-    for y in 0..10 {
-        for x in 0..50 {
-            print!("{}", [' ', 'x'][grid[[x, y]] as usize]);
-        }
-        println!();
-    }
+    answer('B', to_image(&grid).to_ascii(&[' ', 'x']));
 
     Ok(())
 }
@@ -89,7 +175,7 @@ pub(crate) fn run(lines: Lines) -> Result {
 mod tests {
     use super::*;
 
-    fn input() -> (Array2<bool>, Vec<Instruction>) {
+    fn input() -> (BitGrid, Vec<Instruction>) {
         let lines = [
             "6,10",
             "0,14",
@@ -123,11 +209,78 @@ mod tests {
     #[test]
     fn test_a() {
         let (grid, instrs) = input();
-        assert_eq!(count_after_one_fold(&grid, instrs[0]), 17);
+        assert_eq!((grid.nrows(), grid.ncols()), (15, 11));
+        assert_eq!(count_after_one_fold(&grid, instrs[0]).unwrap(), 17);
+
+        // Folding beyond the last dot changes nothing.
+        assert_eq!(fold_x(&grid, 11), grid);
+        assert_eq!(fold_y(&grid, 20), grid);
+
+        // Both folds leave a square.
+        let mut grid = grid;
+        fold(&mut grid, &instrs).unwrap();
+        assert_eq!(
+            grid.resize(5, 5),
+            BitGrid::from_fn(5, 5, |[i, j]| i % 4 == 0 || j % 4 == 0)
+        );
+        assert_eq!(grid.count_ones(), 16);
     }
 
     #[test]
     fn test_b() {
         //
     }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let (n, m) = (rng.random_range(1..30), rng.random_range(1..30));
+            let mut grid = BitGrid::from_fn(n, m, |_| rng.random_bool(0.2));
+            grid.set([n - 1, rng.random_range(0..m)], true);
+            grid.set([rng.random_range(0..n), m - 1], true);
+            let text = format_grid(&grid);
+
+            assert_eq!(parse_grid(&text.lines().collect_vec()).unwrap(), grid);
+
+            let instr = Instruction {
+                axis: ['x', 'y'][rng.random_range(0..2)],
+                pos: rng.random_range(0..1000),
+            };
+            let line = instr.to_string();
+
+            assert_eq!(parse_instrs(&[&line]).unwrap(), [instr]);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "6,10",
+                "0,14",
+                "9,10",
+                "0,3",
+                "10,4",
+                "4,11",
+                "6,0",
+                "6,12",
+                "4,1",
+                "0,13",
+                "10,12",
+                "3,4",
+                "3,0",
+                "8,4",
+                "1,10",
+                "2,14",
+                "8,10",
+                "9,0",
+                "",
+                "fold along y=7",
+                "fold along x=5",
+            ],
+            run,
+        );
+    }
 }
@@ -1,63 +1,113 @@
 use crate::common::*;
-use defaultmap::DefaultHashMap;
-use recap::Recap;
-use serde::Deserialize;
+use std::fmt::{self, Display};
 use std::str::FromStr;
 
-#[derive(Debug, Deserialize, PartialEq, Recap, Copy, Clone)]
-#[recap(regex = r#"(?P<lhs>[A-Z])(?P<rhs>[A-Z]) -> (?P<output>[A-Z])"#)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 struct Rule {
     lhs: char,
     rhs: char,
     output: char,
 }
 
+impl FromStr for Rule {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (lhs, rhs, output) = scan!("{char}{char} -> {char}", line)?;
+        Ok(Rule { lhs, rhs, output })
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{} -> {}", self.lhs, self.rhs, self.output)
+    }
+}
+
 fn parse(lines: Lines) -> Result<Vec<Rule>> {
-    lines.iter().map(|line| Ok(Rule::from_str(line)?)).collect()
+    lines.iter().map(|line| Rule::from_str(line)).collect()
 }
 
-fn count_most_minus_least(input: &str, rules: &[Rule], steps: usize) -> usize {
-    let mut current = DefaultHashMap::<_, usize>::new(0);
+/// Advances the pair counts by one step, `successors` lists the pairs that each pair turns into.
+fn step(current: &[u128], successors: &[Vec<usize>]) -> Result<Vec<u128>> {
+    let mut next = vec![0u128; current.len()];
 
-    let last = input.chars().next_back().unwrap();
-    current[(last, '\0')] += 1; // Add dummy to ensure final character is counted
+    for (from, &n) in enumerate(current) {
+        for &to in &successors[from] {
+            next[to] = next[to].checked_add(n).context("polymer is too long")?;
+        }
+    }
+
+    Ok(next)
+}
 
-    for (a, b) in input.chars().tuple_windows() {
-        current[(a, b)] += 1;
+fn count_most_minus_least(input: &str, rules: &[Rule], steps: u64) -> Result<u128> {
+    ensure!(!input.is_empty(), "empty polymer template");
+
+    let rules: HashMap<_, _> = map(rules, |r| ((r.lhs, r.rhs), r.output)).collect();
+
+    // The null character is a dummy that follows the final character, so that every character
+    // is the first of exactly one pair.
+    let start = input.chars().chain(['\0']).tuple_windows().collect_vec();
+
+    // Only the pairs that can appear in the polymer are numbered, in the order they are reached.
+    let mut pairs = start.iter().copied().unique().collect_vec();
+    let mut index: HashMap<_, _> = enumerate(&pairs).map(|(i, &pair)| (pair, i)).collect();
+    let mut successors = vec![];
+
+    while let Some(&(a, b)) = pairs.get(successors.len()) {
+        // Every pair either turns into two new pairs or stays as it is.
+        let next = match rules.get(&(a, b)) {
+            Some(&x) => vec![(a, x), (x, b)],
+            None => vec![(a, b)],
+        };
+
+        let next = map(next, |pair| {
+            *index.entry(pair).or_insert_with(|| {
+                pairs.push(pair);
+                pairs.len() - 1
+            })
+        });
+        successors.push(next.collect_vec());
     }
 
-    for _ in 0..steps {
-        let mut next = DefaultHashMap::<_, usize>::new(0);
-
-        for ((a, b), count) in current.drain() {
-            if let Some(rule) = find(rules, |r| [r.lhs, r.rhs] == [a, b]) {
-                let x = rule.output;
-                next[(a, x)] += count;
-                next[(x, b)] += count;
-            } else {
-                next[(a, b)] += count;
-            }
-        }
+    let mut current = vec![0; pairs.len()];
+    for pair in &start {
+        current[index[pair]] += 1;
+    }
 
-        current = next;
+    // A step is linear in the number of pairs and a matrix product is cubic, so the matrix only
+    // pays off for very many steps.
+    let n = pairs.len();
+    if steps <= (n * n) as u64 {
+        for _ in 0..steps {
+            current = step(&current, &successors)?;
+        }
+    } else {
+        let transition = Matrix::from_fn(n, |to, from| {
+            successors[from].iter().filter(|&&j| j == to).count() as u128
+        });
+        current = transition.pow(steps)?.apply(&current)?;
     }
 
-    let mut counts = DefaultHashMap::new(0);
+    let mut counts = Counter::<char, u128>::default();
 
-    for ((a, _), c) in current.drain() {
-        counts[a] += c;
+    for (&(a, _), n) in pairs.iter().zip(current).filter(|&(_, n)| n > 0) {
+        counts.try_add_n(a, n).context("polymer is too long")?;
     }
 
-    let counts = counts.values().copied().sorted().collect_vec();
-    counts.last().unwrap() - counts.first().unwrap()
+    let (_, most) = counts.max().unwrap();
+    let (_, least) = counts.min().unwrap();
+    Ok(most - least)
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
+    ensure!(lines.len() > 2, "invalid input");
     let input = lines[0];
     let rules = parse(&lines[2..])?;
 
-    println!("part A: {:?}", count_most_minus_least(input, &rules, 10));
-    println!("part B: {:?}", count_most_minus_least(input, &rules, 40));
+    answer('A', count_most_minus_least(input, &rules, 10)?);
+    answer('B', count_most_minus_least(input, &rules, 40)?);
 
     Ok(())
 }
@@ -79,12 +129,60 @@ mod tests {
     #[test]
     fn test_a() {
         let (input, rules) = input();
-        assert_eq!(count_most_minus_least(&input, &rules, 10), 1588);
+        assert_eq!(count_most_minus_least(&input, &rules, 10).unwrap(), 1588);
     }
 
     #[test]
     fn test_b() {
         let (input, rules) = input();
-        assert_eq!(count_most_minus_least(&input, &rules, 40), 2188189693529);
+        assert_eq!(
+            count_most_minus_least(&input, &rules, 40).unwrap(),
+            2188189693529
+        );
+    }
+
+    #[test]
+    fn test_many_steps() {
+        // The polymer grows by one character per step. With three pairs, more than nine steps
+        // take the matrix path.
+        let rules = parse(&["AB -> A"]).unwrap();
+        for steps in [0, 9, 10, 1_000_000_000_000_000] {
+            assert_eq!(
+                count_most_minus_least("AB", &rules, steps).unwrap(),
+                steps as u128
+            );
+        }
+
+        let (input, rules) = input();
+        assert!(count_most_minus_least(&input, &rules, 1000).is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+        let mut letter = || (b'A' + rng.random_range(0..26)) as char;
+
+        for _ in 0..100 {
+            let rule = Rule {
+                lhs: letter(),
+                rhs: letter(),
+                output: letter(),
+            };
+            let line = rule.to_string();
+
+            assert_eq!(parse(&[&line]).unwrap(), [rule]);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "NNCB", "", "CH -> B", "HH -> N", "CB -> H", "NH -> C", "HB -> C", "HC -> B",
+                "HN -> C", "NN -> C", "BH -> H", "NC -> B", "NB -> B", "BN -> B", "BB -> N",
+                "BC -> B", "CC -> N", "CN -> C",
+            ],
+            run,
+        );
     }
 }
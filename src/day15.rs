@@ -1,82 +1,72 @@
 use crate::common::*;
-use binary_heap_plus::BinaryHeap;
-use defaultmap::DefaultHashMap;
-use ndarray::{Array2, ArrayView2};
-use std::cmp::Reverse;
-
-fn parse(lines: Lines) -> Result<Array2<u32>> {
-    let h = lines.len();
-    let w = lines[0].len();
-    let mut map = Array2::from_elem((w, h), 0);
-
-    for (y, line) in enumerate(lines) {
-        ensure!(line.len() == w, "invalid input");
-
-        for (x, c) in enumerate(line.chars()) {
-            map[[x, y]] = c
-                .to_digit(10)
-                .ok_or_else(|| anyhow!("invalid digit: {:?}", c))?;
-        }
-    }
 
-    Ok(map)
+fn parse(lines: Lines) -> Result<Grid<u32>> {
+    Grid::from_lines(lines, |c| {
+        c.to_digit(10)
+            .filter(|&d| d > 0)
+            .ok_or_else(|| anyhow!("invalid digit: {:?}", c))
+    })
 }
 
-fn grow_map(input: ArrayView2<u32>) -> Array2<u32> {
-    const FACTOR: usize = 5;
-    let (n, m) = input.dim();
-    let mut output = Array2::from_elem((n * FACTOR, m * FACTOR), 0);
-
-    for a in 0..FACTOR {
-        for b in 0..FACTOR {
-            for x in 0..n {
-                for y in 0..m {
-                    output[[a * n + x, b * m + y]] = (input[[x, y]] + (a + b) as u32 - 1) % 9 + 1;
-                }
-            }
-        }
-    }
-
-    output
+#[allow(dead_code)]
+fn format(map: &Grid<u32>) -> String {
+    map.to_string()
 }
 
-fn neighbors<T>(map: ArrayView2<T>, [x, y]: [usize; 2]) -> impl Iterator<Item = [usize; 2]> {
-    let (n, m) = map.dim();
+fn grow_map(input: &Grid<u32>) -> Grid<u32> {
+    const FACTOR: usize = 5;
+    let (n, m) = (input.nrows(), input.ncols());
 
-    [[0, 1], [0, -1], [1, 0], [-1, 0]]
-        .iter()
-        .map(move |&[dx, dy]| [x as isize + dx, y as isize + dy])
-        .filter_map(|[x, y]| Some([x.try_into().ok()?, y.try_into().ok()?]))
-        .filter(move |&[x, y]| x < n && y < m)
+    Grid::from_fn(n * FACTOR, m * FACTOR, |[i, j]| {
+        let (a, b) = (i / n, j / m);
+        (input[[i % n, j % m]] + (a + b) as u32 - 1) % 9 + 1
+    })
 }
 
-fn lowest_risk(map: ArrayView2<u32>) -> u32 {
-    let mut risk = DefaultHashMap::new(u32::MAX);
-    let mut queue = BinaryHeap::new_by_key(|&(_, risk)| Reverse(risk));
+/// Finds the path from the top left to the bottom right with the lowest total risk.
+fn lowest_risk_path(map: &Grid<u32>) -> (u32, Vec<Pos>) {
+    let target = [map.nrows() - 1, map.ncols() - 1];
+
+    // Every step has a risk of at least one, so the distance to the target is a lower bound.
+    astar(
+        [0, 0],
+        |&pos| map.neighbors4(pos).map(|next| (next, map[next])),
+        |&[i, j]| (target[0] - i + target[1] - j) as u32,
+        |&pos| pos == target,
+    )
+    .unwrap() // The target can always be reached.
+}
 
-    queue.push(([0, 0], 0));
+fn lowest_risk(map: &Grid<u32>) -> u32 {
+    lowest_risk_path(map).0
+}
 
-    while let Some((pos, current)) = queue.pop() {
-        if current >= risk[pos] {
-            continue;
-        }
+/// Renders the risk levels in gray with the lowest risk path in red.
+fn render(name: &str, map: &Grid<u32>) -> Result {
+    if !is_rendering() {
+        return Ok(());
+    }
 
-        risk[pos] = current;
+    let mut image = Image::from_fn(map.ncols(), map.nrows(), |x, y| {
+        shade(10 - map[[y, x]] as usize, 9)
+    });
 
-        for neighbor in neighbors(map.view(), pos) {
-            queue.push((neighbor, current + map[neighbor]));
-        }
+    for [i, j] in lowest_risk_path(map).1 {
+        image.set(j, i, [255, 0, 0]);
     }
 
-    let (n, m) = map.dim();
-    risk[[n - 1, m - 1]]
+    save_image(name, &image)
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let map = parse(lines)?;
+    let full_map = grow_map(&map);
 
-    println!("part A: {:?}", lowest_risk(map.view()));
-    println!("part B: {:?}", lowest_risk(grow_map(map.view()).view()));
+    answer('A', lowest_risk(&map));
+    answer('B', lowest_risk(&full_map));
+
+    render("day15a", &map)?;
+    render("day15b", &full_map)?;
 
     Ok(())
 }
@@ -85,7 +75,7 @@ pub(crate) fn run(lines: Lines) -> Result {
 mod tests {
     use super::*;
 
-    fn input() -> Array2<u32> {
+    fn input() -> Grid<u32> {
         let lines = [
             "1163751742",
             "1381373672",
@@ -104,11 +94,32 @@ mod tests {
 
     #[test]
     fn test_a() {
-        assert_eq!(lowest_risk(input().view()), 40);
+        assert_eq!(lowest_risk(&input()), 40);
     }
 
     #[test]
     fn test_b() {
-        assert_eq!(lowest_risk(grow_map(input().view()).view()), 315);
+        assert_eq!(lowest_risk(&grow_map(&input())), 315);
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let (n, m) = (rng.random_range(1..20), rng.random_range(1..20));
+            let map = Grid::from_fn(n, m, |_| rng.random_range(1..=9));
+            let text = format(&map);
+
+            assert_eq!(parse(&text.lines().collect_vec()).unwrap(), map);
+        }
+
+        let lines = input();
+        assert_eq!(format(&lines).lines().next(), Some("1163751742"));
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(&["11637", "13813", "21365", "36949", "74634"], run);
     }
 }
@@ -1,96 +1,77 @@
 use crate::common::*;
-use std::str::Chars;
+use std::fmt::{self, Display};
 
 type Num = u64;
 
-#[derive(Clone)]
-struct BitStream<'a> {
-    index: usize,
-    input: Chars<'a>,
-    buffer: [bool; 4],
-    buffer_index: usize,
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Packet {
+    version: Num,
+    typeid: Num,
+    content: Content,
 }
 
-impl<'a> BitStream<'a> {
-    fn new(input: &'a str) -> Result<Self> {
-        for c in input.chars() {
-            if !c.is_ascii_hexdigit() {
-                bail!("invalid input: {:?}", input);
-            }
-        }
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Content {
+    Literal(Num),
+    Sequence(Vec<Packet>),
+}
 
-        Ok(Self {
-            index: 0,
-            input: input.chars(),
-            buffer: [false; 4],
-            buffer_index: 4,
-        })
-    }
+fn encode(packet: &Packet, writer: &mut BitWriter) {
+    writer.write_bits(packet.version, 3);
+    writer.write_bits(packet.typeid, 3);
 
-    fn index(&self) -> usize {
-        self.index
-    }
-
-    fn next_number(&mut self, bits: usize) -> Option<Num> {
-        let mut output = 0;
+    match &packet.content {
+        &Content::Literal(x) => {
+            let groups = (1..=16).find(|&g| g == 16 || x >> (4 * g) == 0).unwrap();
 
-        for _ in 0..bits {
-            output = (output << 1) | (self.next()? as Num);
+            for i in (0..groups).rev() {
+                writer.write_bit(i > 0);
+                writer.write_bits((x >> (4 * i)) & 0xf, 4);
+            }
         }
+        Content::Sequence(children) if children.len() < 1 << 11 => {
+            writer.write_bit(true);
+            writer.write_bits(children.len() as Num, 11);
 
-        Some(output)
-    }
-}
-
-impl Iterator for BitStream<'_> {
-    type Item = bool;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.buffer_index >= self.buffer.len() {
-            let c = self.input.next()?.to_digit(16).unwrap();
+            for child in children {
+                encode(child, writer);
+            }
+        }
+        Content::Sequence(children) => {
+            writer.write_bit(false);
+            let start = writer.position();
+            writer.write_bits(0, 15);
 
-            for i in 0..4 {
-                self.buffer[i] = (c & (0x8 >> i)) != 0;
+            for child in children {
+                encode(child, writer);
             }
 
-            self.buffer_index = 0;
+            let length = writer.position() - start - 15;
+            writer.set_bits(start, length as Num, 15);
         }
-
-        let output = self.buffer[self.buffer_index];
-        self.buffer_index += 1;
-        self.index += 1;
-        return Some(output);
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-struct Packet {
-    version: Num,
-    typeid: Num,
-    content: Content,
-}
+/// Formats the packet as a hexadecimal transmission, padded with zeros to a whole byte.
+impl Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut writer = BitWriter::new();
+        encode(self, &mut writer);
+        writer.align(8);
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Content {
-    Literal(Num),
-    Sequence(Vec<Packet>),
+        write!(f, "{}", writer.to_hex())
+    }
 }
 
-fn parse(stream: &mut BitStream) -> Result<Packet> {
-    let version = stream
-        .next_number(3)
-        .ok_or_else(|| anyhow!("invalid version"))?;
-    let typeid = stream
-        .next_number(3)
-        .ok_or_else(|| anyhow!("invalid typeid"))?;
+fn parse(reader: &mut BitReader) -> Result<Packet> {
+    let version = reader.read_bits(3).context("invalid version")?;
+    let typeid = reader.read_bits(3).context("invalid typeid")?;
 
     let content = if typeid == 4 {
         let mut x = 0;
         loop {
-            let continuation = stream.next().ok_or_else(|| anyhow!("invalid flag"))?;
-            let y = stream
-                .next_number(4)
-                .ok_or_else(|| anyhow!("invalid chunk"))?;
+            let continuation = reader.read_bit().context("invalid flag")?;
+            let y = reader.read_bits(4).context("invalid chunk")?;
             x = (x << 4) | y;
 
             if !continuation {
@@ -100,28 +81,28 @@ fn parse(stream: &mut BitStream) -> Result<Packet> {
 
         Content::Literal(x)
     } else {
-        let lengthid = stream.next().ok_or_else(|| anyhow!("invalid lengthid"))?;
+        let lengthid = reader.read_bit().context("invalid lengthid")?;
         let mut subpackets = vec![];
 
         if lengthid {
-            let n = stream
-                .next_number(11)
-                .ok_or_else(|| anyhow!("invalid length"))?;
+            let n = reader.read_bits(11).context("invalid length")?;
 
             for _ in 0..n {
-                subpackets.push(parse(stream)?);
+                subpackets.push(parse(reader)?);
             }
         } else {
-            let n = stream
-                .next_number(15)
-                .ok_or_else(|| anyhow!("invalid length"))? as usize;
-            let start = stream.index();
+            let n = reader.read_bits(15).context("invalid length")? as usize;
+            let start = reader.position();
 
-            while stream.index() < start + n {
-                subpackets.push(parse(stream)?);
+            while reader.position() < start + n {
+                subpackets.push(parse(reader)?);
             }
 
-            assert_eq!(stream.index(), start + n);
+            ensure!(
+                reader.position() == start + n,
+                "subpackets exceed length: {}",
+                n
+            );
         }
 
         Content::Sequence(subpackets)
@@ -155,9 +136,23 @@ fn eval(packet: &Packet) -> Result<Num> {
         Content::Sequence(children) => map(children, |x| eval(x)).collect::<Result<Vec<_>>>()?,
     };
 
+    if matches!(packet.typeid, 5..=7) {
+        ensure!(
+            children.len() == 2,
+            "comparison requires two subpackets, found {}",
+            children.len()
+        );
+    }
+
     Ok(match packet.typeid {
-        0 => children.into_iter().sum(),
-        1 => children.into_iter().product(),
+        0 => children
+            .into_iter()
+            .try_fold(0, Num::checked_add)
+            .context("sum overflows")?,
+        1 => children
+            .into_iter()
+            .try_fold(1, Num::checked_mul)
+            .context("product overflows")?,
         2 => children.into_iter().min().unwrap_or_default(),
         3 => children.into_iter().max().unwrap_or_default(),
         5 => (children[0] > children[1]) as _,
@@ -168,10 +163,10 @@ fn eval(packet: &Packet) -> Result<Num> {
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
-    let p = parse(&mut BitStream::new(lines[0])?)?;
+    let p = parse(&mut BitReader::from_hex(lines[0])?)?;
 
-    println!("part A: {:?}", sum_versions(&p));
-    println!("part B: {:?}", eval(&p)?);
+    answer('A', sum_versions(&p));
+    answer('B', eval(&p)?);
     Ok(())
 }
 
@@ -180,7 +175,7 @@ mod tests {
     use super::*;
 
     fn p(input: &str) -> Packet {
-        parse(&mut BitStream::new(input).unwrap()).unwrap()
+        parse(&mut BitReader::from_hex(input).unwrap()).unwrap()
     }
 
     #[test]
@@ -259,4 +254,51 @@ mod tests {
         assert_eq!(eval(&p("9C005AC2F8F0")).unwrap(), 0);
         assert_eq!(eval(&p("9C0141080250320F1802104A08")).unwrap(), 1);
     }
+
+    #[test]
+    fn test_format() {
+        fn generate(rng: &mut impl RngExt, depth: u32) -> Packet {
+            let version = rng.random_range(0..8);
+
+            if depth > 3 || rng.random_bool(0.4) {
+                let literal = match rng.random_range(0..3) {
+                    0 => rng.random_range(0..16),
+                    1 => rng.random_range(0..1 << 20),
+                    _ => rng.random(),
+                };
+
+                Packet {
+                    version,
+                    typeid: 4,
+                    content: Content::Literal(literal),
+                }
+            } else {
+                let typeid = [0, 1, 2, 3, 5, 6, 7][rng.random_range(0..7)];
+                let children = (0..rng.random_range(1..4))
+                    .map(|_| generate(rng, depth + 1))
+                    .collect();
+
+                Packet {
+                    version,
+                    typeid,
+                    content: Content::Sequence(children),
+                }
+            }
+        }
+
+        assert_eq!(p("D2FE28").to_string(), "D2FE28");
+        assert_eq!(p("EE00D40C823060").to_string(), "EE00D40C823060");
+
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let packet = generate(&mut rng, 0);
+            assert_eq!(p(&packet.to_string()), packet);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(&["9C0141080250320F1802104A08"], run);
+    }
 }
@@ -1,13 +1,11 @@
 use crate::common::*;
-use recap::Recap;
-use serde::Deserialize;
+use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 
 type Num = i32;
 
-#[derive(Debug, Deserialize, PartialEq, Recap, Copy, Clone)]
-#[recap(
-    regex = r#"target area: x=(?P<x0>[0-9]+)..(?P<x1>[0-9]+), y=(?P<y0>-[0-9]+)..(?P<y1>-[0-9]+)"#
-)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 struct Target {
     x0: Num,
     y0: Num,
@@ -15,73 +13,98 @@ struct Target {
     y1: Num,
 }
 
-fn simulate(mut vx: Num, mut vy: Num, target: Target) -> Option<Num> {
-    let (mut x, mut y) = (0, 0);
+impl FromStr for Target {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (x0, x1, y0, y1) = scan!("target area: x={i32}..{i32}, y={i32}..{i32}", line)?;
+
+        // The target must be in front of and below the probe.
+        ensure!(
+            x0 >= 0 && x1 >= 0,
+            "target must have a positive x: {:?}",
+            line
+        );
+        ensure!(
+            y0 < 0 && y1 < 0,
+            "target must have a negative y: {:?}",
+            line
+        );
+
+        Ok(Target { x0, y0, x1, y1 })
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "target area: x={}..{}, y={}..{}",
+            self.x0, self.x1, self.y0, self.y1
+        )
+    }
+}
+
+impl Target {
+    fn region(&self) -> Aabb<2> {
+        let low = [self.x0, self.y0].map(i64::from);
+        let high = [self.x1, self.y1].map(i64::from);
+
+        Aabb::inclusive(low, high)
+    }
+}
+
+fn simulate(mut velocity: Point2, target: Target) -> Option<i64> {
+    let [xs, ys] = target.region().axes;
+    let mut pos = Point2::ZERO;
     let mut max_y = 0;
 
     loop {
-        x += vx;
-        y += vy;
-        max_y = max_y.max(y);
-
-        if vx > 0 {
-            vx -= 1;
-        } else if vx < 0 {
-            vx += 1;
-        }
+        pos += velocity;
+        max_y = max_y.max(pos.y);
 
-        vy -= 1;
+        // Drag slows the probe down horizontally and gravity pulls it down.
+        velocity -= Point2::new(velocity.x.signum(), 1);
 
-        let in_x = (target.x0..=target.x1).contains(&x);
-        let in_y = (target.y0..=target.y1).contains(&y);
+        let in_x = xs.contains(pos.x);
+        let in_y = ys.contains(pos.y);
 
         if in_x && in_y {
             return Some(max_y);
         }
 
-        if !in_x && vx == 0 {
+        if !in_x && velocity.x == 0 {
             return None;
         }
 
-        if !in_y && vy < 0 && y < target.y0 {
+        if !in_y && velocity.y < 0 && pos.y < ys.start {
             return None;
         }
     }
 }
 
-fn highest_position(target: Target) -> Num {
-    let mut best = 0;
-
-    for vx in 0..=100 {
-        for vy in 0..=100 {
-            if let Some(max_y) = simulate(vx, vy, target) {
-                if max_y > best {
-                    best = max_y
-                }
-            }
-        }
-    }
-
-    best
+fn velocities(xs: RangeInclusive<i64>, ys: RangeInclusive<i64>) -> Vec<Point2> {
+    xs.cartesian_product(ys)
+        .map(|(vx, vy)| Point2::new(vx, vy))
+        .collect()
 }
 
-fn number_velocities(target: Target) -> Num {
-    let mut total = 0;
-
-    for vx in 0..=350 {
-        for vy in -100..=100 {
-            total += simulate(vx, vy, target).is_some() as Num;
-        }
-    }
+fn highest_position(target: Target) -> i64 {
+    let heights = par_map(&velocities(0..=100, 0..=100), |&v| simulate(v, target));
+    heights.into_iter().flatten().fold(0, i64::max)
+}
 
-    total
+fn number_velocities(target: Target) -> usize {
+    par_sum(&velocities(0..=350, -100..=100), |&v| {
+        simulate(v, target).is_some() as usize
+    })
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let target = lines[0].parse()?;
 
-    println!("part A: {:?}", highest_position(target));
-    println!("part B: {:?}", number_velocities(target));
+    answer('A', highest_position(target));
+    answer('B', number_velocities(target));
 
     Ok(())
 }
@@ -103,4 +126,27 @@ mod tests {
     fn test_b() {
         assert_eq!(number_velocities(input()), 112);
     }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(input().to_string(), "target area: x=20..30, y=-10..-5");
+
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let target = Target {
+                x0: rng.random_range(0..100),
+                x1: rng.random_range(100..200),
+                y0: rng.random_range(-200..-100),
+                y1: rng.random_range(-100..-1),
+            };
+
+            assert_eq!(target.to_string().parse::<Target>().unwrap(), target);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(&["target area: x=20..30, y=-10..-5"], run);
+    }
 }
@@ -4,7 +4,7 @@ use std::str::Chars;
 
 type Num = i64;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum SnailNum {
     Value(Num),
     Pair(Box<SnailNum>, Box<SnailNum>),
@@ -67,15 +67,16 @@ fn apply_explode(num: &mut SnailNum) -> bool {
     }
 
     fn recur(num: &mut SnailNum, depth: u32) -> Return {
-        match num {
-            Pair(l, r) if depth > 3 => {
-                if let (&Value(x), &Value(y)) = (&**l, &**r) {
-                    *num = Value(0);
-                    Explode(x, y)
-                } else {
-                    panic!("invalid snail number");
-                }
+        if let Pair(l, r) = num {
+            if let (true, &Value(x), &Value(y)) = (depth > 3, &**l, &**r) {
+                *num = Value(0);
+                return Explode(x, y);
             }
+        }
+
+        match num {
+            // Pairs nested deeper than four levels only occur in malformed input, these explode
+            // their innermost pairs first.
             Pair(l, r) => {
                 if let Explode(x, y) = recur(l, depth + 1) {
                     apply(r, Left(y));
@@ -123,30 +124,32 @@ fn add(left: SnailNum, right: SnailNum) -> SnailNum {
     output
 }
 
-fn sum(numbers: &[SnailNum]) -> SnailNum {
-    let mut output = numbers[0].clone();
+fn sum(numbers: &[SnailNum]) -> Result<SnailNum> {
+    let (first, rest) = numbers.split_first().context("no snail numbers")?;
+    let mut output = first.clone();
 
-    for number in &numbers[1..] {
+    for number in rest {
         output = add(output, number.clone());
     }
 
-    output
+    Ok(output)
 }
 
-fn largest_sum(numbers: &[SnailNum]) -> SnailNum {
-    numbers
-        .iter()
-        .cartesian_product(numbers)
-        .map(|(l, r)| add(l.clone(), r.clone()))
-        .max_by_key(magnitude)
-        .unwrap()
+fn largest_sum(numbers: &[SnailNum]) -> Result<SnailNum> {
+    let pairs = (0..numbers.len())
+        .cartesian_product(0..numbers.len())
+        .collect_vec();
+    let sum = |&(i, j): &(usize, usize)| add(numbers[i].clone(), numbers[j].clone());
+
+    let best = par_max_by_key(&pairs, |pair| magnitude(&sum(pair))).context("no snail numbers")?;
+    Ok(sum(best))
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let numbers = parse_lines(lines)?;
 
-    println!("part A: {}", magnitude(&sum(&numbers)));
-    println!("part A: {}", magnitude(&largest_sum(&numbers)));
+    answer('A', magnitude(&sum(&numbers)?));
+    answer('B', magnitude(&largest_sum(&numbers)?));
 
     Ok(())
 }
@@ -192,4 +195,38 @@ mod tests {
     fn test_b() {
         //
     }
+
+    #[test]
+    fn test_format() {
+        fn generate(rng: &mut impl RngExt, depth: u32) -> SnailNum {
+            if depth < 5 && rng.random_bool(0.6) {
+                let left = generate(rng, depth + 1);
+                let right = generate(rng, depth + 1);
+                Pair(Box::new(left), Box::new(right))
+            } else {
+                Value(rng.random_range(0..=9))
+            }
+        }
+
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let num = generate(&mut rng, 0);
+            let line = num.to_string();
+
+            assert_eq!(parse_lines(&[&line]).unwrap(), [num]);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]",
+                "[[[5,[2,8]],4],[5,[[9,9],0]]]",
+                "[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]",
+            ],
+            run,
+        );
+    }
 }
@@ -1,22 +1,11 @@
 use crate::common::*;
-use recap::Recap;
-use serde::Deserialize;
+use std::collections::VecDeque;
 
 type Num = i64;
-type Vec3 = nalgebra::Vector3<Num>;
-type Mat3 = nalgebra::Matrix3<Num>;
-
-#[derive(Hash, Debug, Deserialize, PartialEq, Recap, Copy, Clone, Ord, PartialOrd, Eq)]
-#[recap(regex = r#"(?P<x>-?[0-9]+),(?P<y>-?[0-9]+),(?P<z>-?[0-9]+)"#)]
-struct Beacon {
-    x: Num,
-    y: Num,
-    z: Num,
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 struct Scanner {
-    beacons: Vec<Vec3>,
+    beacons: Vec<Point3>,
 }
 
 fn parse_scanners(lines: Lines) -> Result<Vec<Scanner>> {
@@ -31,8 +20,8 @@ fn parse_scanners(lines: Lines) -> Result<Vec<Scanner>> {
         let beacons = lines
             .by_ref()
             .take_while(|l| !l.is_empty())
-            .map(|l| Ok(l.parse::<Beacon>()?))
-            .map_ok(|p| Vec3::new(p.x, p.y, p.z))
+            .map(|l| scan!("{i64},{i64},{i64}", l))
+            .map_ok(|(x, y, z)| Point3::new(x, y, z))
             .collect::<Result<_>>()?;
 
         scanners.push(Scanner { beacons });
@@ -41,101 +30,99 @@ fn parse_scanners(lines: Lines) -> Result<Vec<Scanner>> {
     Ok(scanners)
 }
 
-fn rotations() -> Vec<Mat3> {
-    const VECTORS: [[Num; 3]; 6] = [
-        [1, 0, 0],
-        [-1, 0, 0],
-        [0, 1, 0],
-        [0, -1, 0],
-        [0, 0, 1],
-        [0, 0, -1],
-    ];
-
-    let mut result = vec![];
-
-    for dx in VECTORS {
-        for dy in VECTORS {
-            let dz = [
-                dx[1] * dy[2] - dx[2] * dy[1],
-                dx[2] * dy[0] - dx[0] * dy[2],
-                dx[0] * dy[1] - dx[1] * dy[0],
-            ];
-
-            if dz[0].abs() + dz[1].abs() + dz[2].abs() != 1 {
-                continue;
-            }
-
-            result.push(Mat3::new(
-                dx[0], dx[1], dx[2], //
-                dy[0], dy[1], dy[2], //
-                dz[0], dz[1], dz[2], //
-            ));
-        }
-    }
-
-    result
+#[allow(dead_code)]
+fn format_scanners(scanners: &[Scanner]) -> String {
+    enumerate(scanners)
+        .map(|(i, scanner)| {
+            let beacons = scanner.beacons.iter().format("\n");
+            format!("--- scanner {} ---\n{}", i, beacons)
+        })
+        .join("\n\n")
 }
 
-fn align_scanners(scanners: &[Scanner]) -> Vec<(Mat3, Vec3)> {
-    let rotations = rotations();
-    let mut count: HashMap<Vec3, usize> = HashMap::default();
-    let mut results = vec![];
+fn align_scanners(scanners: &[Scanner]) -> Result<Vec<(Rotation, Point3)>> {
+    let orientations = (0..scanners.len())
+        .cartesian_product(Rotation::all())
+        .collect_vec();
 
-    for (j, b) in enumerate(scanners) {
-        for &r in &rotations {
-            let rotated = map(&b.beacons, |x| r * x).collect_vec();
+    // For every scanner and rotation, the scanners it overlaps with and the translation.
+    let results = par_map(&orientations, |&(j, &r)| {
+        let rotated = map(&scanners[j].beacons, |&x| r * x).collect_vec();
+        let mut count = Counter::new();
+        let mut results = vec![];
 
-            for (i, a) in enumerate(scanners) {
-                count.clear();
+        for (i, a) in enumerate(scanners) {
+            count.clear();
 
-                for &x in &a.beacons {
-                    for &y in &rotated {
-                        *count.entry(x - y).or_default() += 1;
-                    }
+            for &x in &a.beacons {
+                for &y in &rotated {
+                    count.add(x - y);
                 }
+            }
 
-                for (&t, &freq) in &count {
-                    if freq >= 12 {
-                        results.push((i, j, r, t));
-                    }
+            for (&t, freq) in count.iter() {
+                if freq >= 12 {
+                    results.push((i, j, r, t));
                 }
             }
         }
-    }
+
+        results
+    });
+    let results = results.into_iter().flatten().collect_vec();
 
     let n = scanners.len();
-    let mut valid = vec![false; n];
-    let mut orients = vec![(Mat3::identity(), Vec3::default()); n];
-    valid[0] = true;
-
-    for _ in 0..n {
-        for &(i, j, r, t) in &results {
-            if valid[i] && !valid[j] {
-                valid[j] = true;
-                orients[j] = (
-                    orients[i].0 * r,                //
-                    orients[i].0 * t + orients[i].1, //
-                );
+    ensure!(n > 0, "no scanners found");
+    let mut sets = UnionFind::new(n);
+    for &(i, j, _, _) in &results {
+        sets.union(i, j);
+    }
+
+    ensure!(
+        sets.num_components() <= 1,
+        "scanners form {} groups that cannot be aligned with each other",
+        sets.num_components()
+    );
+
+    // Every match places the second scanner relative to the first one, so starting from scanner 0
+    // each scanner is placed once it is first reached.
+    let mut matches = vec![vec![]; n];
+    for &(i, j, r, t) in &results {
+        matches[i].push((j, r, t));
+    }
+
+    let mut orients = vec![None; n];
+    let mut queue = VecDeque::from([0]);
+    orients[0] = Some((Rotation::IDENTITY, Point3::ZERO));
+
+    while let Some(i) = queue.pop_front() {
+        let (ri, ti) = orients[i].unwrap();
+
+        for &(j, r, t) in &matches[i] {
+            if orients[j].is_none() {
+                orients[j] = Some((ri * r, ri * t + ti));
+                queue.push_back(j);
             }
         }
     }
 
-    orients
+    let orients = orients.into_iter().flatten().collect_vec();
+    Ok(orients)
 }
 
-fn find_beacons(scanners: &[Scanner], orientations: &[(Mat3, Vec3)]) -> Vec<Vec3> {
+fn find_beacons(scanners: &[Scanner], orientations: &[(Rotation, Point3)]) -> Vec<Point3> {
     zip(scanners, orientations)
-        .flat_map(|(scanner, (r, t))| map(&scanner.beacons, move |b| r * b + t))
+        .flat_map(|(scanner, (r, t))| map(&scanner.beacons, move |&b| *r * b + *t))
         .unique()
         .collect()
 }
 
-fn largest_distance(orientations: &[(Mat3, Vec3)]) -> Num {
+fn largest_distance(orientations: &[(Rotation, Point3)]) -> Num {
     let mut largest = 0;
 
     for (_, t1) in orientations {
         for (_, t2) in orientations {
-            largest = (t1 - t2).abs().sum().max(largest);
+            largest = t1.manhattan(*t2).max(largest);
         }
     }
 
@@ -144,10 +131,10 @@ fn largest_distance(orientations: &[(Mat3, Vec3)]) -> Num {
 
 pub(crate) fn run(lines: Lines) -> Result {
     let scanners = parse_scanners(lines)?;
-    let orients = align_scanners(&scanners);
+    let orients = align_scanners(&scanners)?;
 
-    println!("part A: {:?}", find_beacons(&scanners, &orients).len());
-    println!("part B: {:?}", largest_distance(&orients));
+    answer('A', find_beacons(&scanners, &orients).len());
+    answer('B', largest_distance(&orients));
 
     Ok(())
 }
@@ -165,4 +152,43 @@ mod tests {
     fn test_b() {
         //
     }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let scanners = (0..rng.random_range(1..5))
+                .map(|_| Scanner {
+                    beacons: (0..rng.random_range(1..30))
+                        .map(|_| Point3::from([(); 3].map(|_| rng.random_range(-1000..=1000))))
+                        .collect(),
+                })
+                .collect_vec();
+            let text = format_scanners(&scanners);
+
+            assert_eq!(
+                parse_scanners(&text.lines().collect_vec()).unwrap(),
+                scanners
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "--- scanner 0 ---",
+                "404,-588,-901",
+                "528,-643,409",
+                "-838,591,734",
+                "",
+                "--- scanner 1 ---",
+                "686,422,578",
+                "605,423,415",
+                "515,917,-361",
+            ],
+            run,
+        );
+    }
 }
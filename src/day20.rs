@@ -1,70 +1,113 @@
 use crate::common::*;
-use ndarray::Array2;
 
 type Lookup = [bool; 512];
 
-fn parse(lines: Lines) -> Result<(Lookup, Array2<bool>)> {
+fn parse_pixel(c: char) -> Result<bool> {
+    match c {
+        '#' => Ok(true),
+        '.' => Ok(false),
+        _ => bail!("invalid pixel: {:?}", c),
+    }
+}
+
+fn parse(lines: Lines) -> Result<(Lookup, BitGrid)> {
     ensure!(lines.len() > 2);
 
     let mut lookup = [false; 512];
     ensure!(lines[0].len() == lookup.len());
 
     for (i, c) in enumerate(lines[0].chars()) {
-        lookup[i] = c == '#';
+        lookup[i] = parse_pixel(c)?;
     }
 
-    let height = lines.len() - 2;
-    let width = lines[2].len();
-    let mut img = Array2::from_elem((height, width), false);
+    let img = Grid::from_lines(&lines[2..], parse_pixel)?;
+    let img = BitGrid::from_fn(img.nrows(), img.ncols(), |pos| img[pos]);
 
-    for (i, line) in enumerate(&lines[2..]) {
-        ensure!(line.len() == width);
+    Ok((lookup, img))
+}
 
-        for (j, c) in enumerate(line.chars()) {
-            img[[i, j]] = c == '#';
-        }
+fn format_pixel(&b: &bool) -> char {
+    if b {
+        '#'
+    } else {
+        '.'
     }
+}
 
-    Ok((lookup, img))
+#[allow(dead_code)]
+fn format(lookup: &Lookup, img: &BitGrid) -> String {
+    format!(
+        "{}\n\n{}",
+        lookup.iter().map(format_pixel).collect::<String>(),
+        img
+    )
 }
 
-fn simulate(input: &Array2<bool>, lookup: &Lookup, default: bool) -> Array2<bool> {
-    let (n, m) = input.dim();
+/// Every pixel becomes the entry of the lookup table for the 3x3 square around it, so the image
+/// grows by one pixel on every side.
+fn simulate(img: &BitGrid, lookup: &Lookup, background: bool) -> BitGrid {
+    BitGrid::from_fn(img.nrows() + 2, img.ncols() + 2, |[i, j]| {
+        let [i, j] = [i as isize - 1, j as isize - 1];
+        let index = (-1..=1).fold(0, |index, di| {
+            (index << 3) | img.bits([i + di, j - 1], 3, background)
+        });
 
-    Array2::from_shape_fn((n + 2, m + 2), |(i, j)| {
-        let mut index = 0;
+        lookup[index as usize]
+    })
+}
 
-        for di in [2, 1, 0] {
-            for dj in [2, 1, 0] {
-                let flag = if i >= di && j >= dj {
-                    input.get((i - di, j - dj)).copied().unwrap_or(default)
-                } else {
-                    default
-                };
+/// The pixels outside the image, which stretch out infinitely, are either all lit or all dark.
+fn next_background(lookup: &Lookup, background: bool) -> bool {
+    lookup[if background { 511 } else { 0 }]
+}
 
-                index = (index * 2) + (flag as usize);
-            }
-        }
+fn count_after(img: &BitGrid, lookup: &Lookup, iters: usize) -> Result<usize> {
+    // Only the number of lit pixels matters and not their position, so trimming the image allows
+    // still and oscillating images to be detected as a cycle.
+    let (img, background) = state_after((img.clone(), false), iters, |(img, background)| {
+        let img = simulate(img, lookup, *background);
+        let background = next_background(lookup, *background);
+        (img.trim(background), background)
+    });
+
+    ensure!(!background, "infinitely many pixels are lit");
+    Ok(img.count_ones())
+}
 
-        lookup[index]
+fn to_image(img: &BitGrid) -> Image {
+    Image::from_fn(img.ncols(), img.nrows(), |x, y| {
+        if img.get([y, x]) {
+            WHITE
+        } else {
+            BLACK
+        }
     })
 }
 
-fn count_after(img: &Array2<bool>, lookup: &Lookup, iters: usize) -> usize {
+fn render(img: &BitGrid, lookup: &Lookup, iters: usize) -> Result {
+    let mut frames = Frames::new("day20")?;
     let mut img = img.clone();
+    let mut background = false;
 
-    for iter in 0..iters {
-        img = simulate(&img, lookup, lookup[0] && (iter % 2 == 1));
+    if frames.is_enabled() {
+        frames.push(&to_image(&img))?;
+
+        for _ in 0..iters {
+            img = simulate(&img, lookup, background);
+            background = next_background(lookup, background);
+            frames.push(&to_image(&img))?;
+        }
     }
 
-    img.iter().filter(|&&b| b).count()
+    Ok(())
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let (lookup, img) = parse(lines)?;
 
-    println!("part A: {:?}", count_after(&img, &lookup, 2));
-    println!("part B: {:?}", count_after(&img, &lookup, 50));
+    answer('A', count_after(&img, &lookup, 2)?);
+    answer('B', count_after(&img, &lookup, 50)?);
+    render(&img, &lookup, 50)?;
     Ok(())
 }
 
@@ -72,7 +115,7 @@ pub(crate) fn run(lines: Lines) -> Result {
 mod tests {
     use super::*;
 
-    fn input() -> (Lookup, Array2<bool>) {
+    fn input() -> (Lookup, BitGrid) {
         let lines = [
             "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.\
             ###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.\
@@ -95,14 +138,46 @@ mod tests {
     #[test]
     fn test_a() {
         let (lookup, img) = input();
-        assert_eq!(count_after(&img, &lookup, 0), 10);
-        assert_eq!(count_after(&img, &lookup, 1), 24);
-        assert_eq!(count_after(&img, &lookup, 2), 35);
+        assert_eq!(count_after(&img, &lookup, 0).unwrap(), 10);
+        assert_eq!(count_after(&img, &lookup, 1).unwrap(), 24);
+        assert_eq!(count_after(&img, &lookup, 2).unwrap(), 35);
     }
 
     #[test]
     fn test_b() {
         let (lookup, img) = input();
-        assert_eq!(count_after(&img, &lookup, 50), 3351);
+        assert_eq!(count_after(&img, &lookup, 50).unwrap(), 3351);
+
+        // Every pixel keeps its value, or every pixel is inverted.
+        let identity: Lookup = std::array::from_fn(|i| i & 16 != 0);
+        assert_eq!(count_after(&img, &identity, 1_000_000_000_000).unwrap(), 10);
+
+        let inverse: Lookup = std::array::from_fn(|i| i & 16 == 0);
+        assert_eq!(count_after(&img, &inverse, 1_000_000_000_000).unwrap(), 10);
+        assert!(count_after(&img, &inverse, 1_000_000_000_001).is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let mut lookup = [false; 512];
+            lookup.iter_mut().for_each(|b| *b = rng.random());
+
+            let (n, m) = (rng.random_range(1..20), rng.random_range(1..20));
+            let img = BitGrid::from_fn(n, m, |_| rng.random());
+            let text = format(&lookup, &img);
+
+            assert_eq!(parse(&text.lines().collect_vec()).unwrap(), (lookup, img));
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        let (lookup, img) = input();
+        let text = format(&lookup, &img);
+
+        fuzz(&text.lines().collect_vec(), run);
     }
 }
@@ -1,17 +1,26 @@
 use crate::common::*;
 
 pub(crate) fn parse(lines: Lines) -> Result<[u64; 2]> {
-    let a = find_regex("Player 1 starting position: ([0-9])", lines[0])
-        .ok_or_else(|| anyhow!("invalid input"))?[1]
-        .parse()?;
+    ensure!(lines.len() == 2, "invalid input");
 
-    let b = find_regex("Player 2 starting position: ([0-9])", lines[1])
-        .ok_or_else(|| anyhow!("invalid input"))?[1]
-        .parse()?;
+    let (a,) = scan!("Player 1 starting position: {u64}", lines[0])?;
+    let (b,) = scan!("Player 2 starting position: {u64}", lines[1])?;
+
+    for p in [a, b] {
+        ensure!((1..=10).contains(&p), "invalid starting position: {}", p);
+    }
 
     Ok([a, b])
 }
 
+#[allow(dead_code)]
+fn format(spaces: [u64; 2]) -> String {
+    format!(
+        "Player 1 starting position: {}\nPlayer 2 starting position: {}",
+        spaces[0], spaces[1]
+    )
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 struct GameResult {
     spaces: [u64; 2],
@@ -47,83 +56,89 @@ fn play_game(mut spaces: [u64; 2]) -> GameResult {
     }
 }
 
-fn play_quantum_game(spaces: [u64; 2]) -> [u64; 2] {
-    const MAX_SCORE: usize = 21;
-    const MAX_SPACES: usize = 10;
-
-    #[derive(PartialEq, Eq, Debug, Hash, Copy, Clone)]
-    struct State {
-        scores: [u8; 2],
-        spaces: [u8; 2],
-        player: u8,
-    }
+#[derive(PartialEq, Eq, Debug, Hash, Copy, Clone)]
+struct State {
+    scores: [u64; 2],
+    spaces: [u64; 2],
+    player: usize,
+}
 
-    fn state_to_key(state: State) -> usize {
-        let [a, b] = [state.scores[0] as usize, state.scores[1] as usize];
-        let [x, y] = [state.spaces[0] as usize, state.spaces[1] as usize];
-        let p = state.player as usize;
+/// Number of universes in which each player wins, when the players start at the given spaces
+/// (counting from 1) on a board of `board` spaces and play until one reaches `max_score` points.
+fn play_quantum_game(spaces: [u64; 2], board: u64, max_score: u64) -> Result<[u64; 2]> {
+    const THROWS: [(u64, u64); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
+
+    fn recur(
+        memo: &mut Memo<State, [u64; 2]>,
+        state: State,
+        board: u64,
+        max_score: u64,
+    ) -> [u64; 2] {
+        memo.get_or_compute(state, |memo, &state| {
+            let mut result = [0, 0];
+            let player = state.player;
+
+            for (total, times) in THROWS {
+                let mut state = state;
+                state.spaces[player] = (state.spaces[player] + total) % board;
+                state.scores[player] += state.spaces[player] + 1;
+                state.player = 1 - player;
+
+                if state.scores[player] >= max_score {
+                    result[player] += times;
+                } else {
+                    let subresult = recur(memo, state, board, max_score);
+                    result[0] += times * subresult[0];
+                    result[1] += times * subresult[1];
+                }
+            }
 
-        (((a * MAX_SCORE + b) * MAX_SPACES + x) * MAX_SPACES + y) * 2 + p
+            result
+        })
     }
 
-    let mut cache = vec![[0, 0]; MAX_SCORE * MAX_SCORE * MAX_SPACES * MAX_SPACES * 2];
-
-    fn recur(state: State, cache: &mut [[u64; 2]]) -> [u64; 2] {
-        const THROWS: [(u8, u64); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
-        let key = state_to_key(state);
-
-        if cache[key] != [0, 0] {
-            return cache[key];
-        }
-
-        let mut result = [0, 0];
-
-        for (total, times) in THROWS {
-            let mut state = state;
-            let player = state.player as usize;
-            state.spaces[player] = (state.spaces[player] + total) % (MAX_SPACES as u8);
-            state.scores[player] += state.spaces[player] + 1;
-            state.player = 1 - state.player;
-
-            let subresult = if state.scores[player] >= MAX_SCORE as u8 {
-                match player {
-                    0 => [1, 0],
-                    1 => [0, 1],
-                    _ => panic!("invalid player"),
-                }
-            } else {
-                recur(state, cache)
-            };
-
-            result[0] += times * subresult[0];
-            result[1] += times * subresult[1];
-        }
+    // Without points to score, nobody ever gets to take a turn.
+    ensure!(max_score > 0, "the score to reach must be positive");
 
-        cache[key] = result;
-        result
+    for space in spaces {
+        ensure!(
+            (1..=board).contains(&space),
+            "starting space {} is not on a board of {} spaces",
+            space,
+            board
+        );
     }
 
-    recur(
-        State {
-            scores: [0, 0],
-            spaces: [spaces[0] as u8 - 1, spaces[1] as u8 - 1],
-            player: 0,
-        },
-        &mut cache,
-    )
+    // Every state that is not finished yet has scores below the maximum.
+    let (n, m) = (max_score as usize, board as usize);
+    let size = [n, n, m, m, 2]
+        .into_iter()
+        .try_fold(1usize, usize::checked_mul)
+        .context("board or score too large")?;
+    let mut memo = Memo::dense(size, move |state: &State| {
+        let [a, b] = state.scores.map(|s| s as usize);
+        let [x, y] = state.spaces.map(|s| s as usize);
+
+        (((a * n + b) * m + x) * m + y) * 2 + state.player
+    });
+
+    let state = State {
+        scores: [0, 0],
+        spaces: spaces.map(|s| s - 1),
+        player: 0,
+    };
+
+    Ok(recur(&mut memo, state, board, max_score))
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let spaces = parse(lines)?;
     let result = play_game(spaces);
 
-    println!(
-        "part A: {:?}",
-        result.throws * result.scores[1 - result.winner]
-    );
+    answer('A', result.throws * result.scores[1 - result.winner]);
 
-    let result = play_quantum_game(spaces);
-    println!("part B: {:?}", u64::max(result[0], result[1]));
+    let result = play_quantum_game(spaces, 10, 21)?;
+    answer('B', u64::max(result[0], result[1]));
 
     Ok(())
 }
@@ -131,6 +146,7 @@ pub(crate) fn run(lines: Lines) -> Result {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::iproduct;
 
     #[test]
     fn test_a() {
@@ -148,7 +164,71 @@ mod tests {
 
     #[test]
     fn test_b() {
-        let result = play_quantum_game([4, 8]);
+        let result = play_quantum_game([4, 8], 10, 21).unwrap();
         assert_eq!(result, [444356092776315, 341960390180808]);
+
+        // The first player always wins when a single turn is enough.
+        assert_eq!(play_quantum_game([1, 1], 10, 1).unwrap(), [27, 0]);
+
+        assert!(play_quantum_game([1, 1], 10, 0).is_err());
+        assert!(play_quantum_game([0, 1], 10, 21).is_err());
+        assert!(play_quantum_game([1, 11], 10, 21).is_err());
+        assert!(play_quantum_game([1, 1], 0, 21).is_err());
+
+        // Compare with rolling every die separately for some small boards and scores.
+        fn naive(spaces: [u64; 2], scores: [u64; 2], board: u64, max_score: u64) -> [u64; 2] {
+            let mut result = [0, 0];
+
+            for (a, b, c) in iproduct!(1..=3, 1..=3, 1..=3) {
+                let (mut spaces, mut scores) = (spaces, scores);
+                spaces[0] = (spaces[0] + a + b + c - 1) % board + 1;
+                scores[0] += spaces[0];
+
+                if scores[0] >= max_score {
+                    result[0] += 1;
+                } else {
+                    let [y, x] = naive(
+                        [spaces[1], spaces[0]],
+                        [scores[1], scores[0]],
+                        board,
+                        max_score,
+                    );
+                    result[0] += x;
+                    result[1] += y;
+                }
+            }
+
+            result
+        }
+
+        for (board, max_score) in [(1, 2), (2, 3), (3, 3), (10, 2)] {
+            for (a, b) in iproduct!(1..=board, 1..=board) {
+                assert_eq!(
+                    play_quantum_game([a, b], board, max_score).unwrap(),
+                    naive([a, b], [0, 0], board, max_score)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        for a in 1..=10 {
+            for b in 1..=10 {
+                let text = format([a, b]);
+                assert_eq!(parse(&text.lines().collect_vec()).unwrap(), [a, b]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "Player 1 starting position: 4",
+                "Player 2 starting position: 8",
+            ],
+            run,
+        );
     }
 }
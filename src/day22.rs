@@ -1,15 +1,10 @@
 use crate::common::*;
-use recap::Recap;
-use serde::Deserialize;
-use std::mem::take;
-use std::ops::Range;
+use std::fmt::{self, Display};
+use std::str::FromStr;
 
 type Num = i64;
 
-#[derive(Hash, Debug, Deserialize, PartialEq, Recap, Clone, Ord, PartialOrd, Eq)]
-#[recap(
-    regex = r#"(?P<action>(on|off)) x=(?P<x0>-?[0-9]+)..(?P<x1>-?[0-9]+),y=(?P<y0>-?[0-9]+)..(?P<y1>-?[0-9]+),z=(?P<z0>-?[0-9]+)..(?P<z1>-?[0-9]+)"#
-)]
+#[derive(Hash, Debug, PartialEq, Clone, Ord, PartialOrd, Eq)]
 struct Instr {
     action: String,
     x0: Num,
@@ -20,8 +15,44 @@ struct Instr {
     z1: Num,
 }
 
+impl FromStr for Instr {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (action, x0, x1, y0, y1, z0, z1) = scan!(
+            "{String} x={i64}..{i64},y={i64}..{i64},z={i64}..{i64}",
+            line
+        )?;
+        ensure!(
+            action == "on" || action == "off",
+            "invalid action: {:?}",
+            action
+        );
+
+        Ok(Instr {
+            action,
+            x0,
+            y0,
+            z0,
+            x1,
+            y1,
+            z1,
+        })
+    }
+}
+
+impl Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} x={}..{},y={}..{},z={}..{}",
+            self.action, self.x0, self.x1, self.y0, self.y1, self.z0, self.z1
+        )
+    }
+}
+
 fn parse(lines: Lines) -> Result<Vec<Instr>> {
-    lines.iter().map(|l| Ok(l.parse()?)).collect()
+    lines.iter().map(|l| l.parse()).collect()
 }
 
 /*
@@ -54,111 +85,44 @@ fn execute(instructions: &[Instr]) -> usize {
 }
 */
 
-#[derive(Clone, Debug)]
-struct Cube {
-    x: Range<Num>,
-    y: Range<Num>,
-    z: Range<Num>,
-}
-
-impl Cube {
-    fn from_bounds(low: Num, high: Num) -> Self {
-        Self {
-            x: low..(high + 1),
-            y: low..(high + 1),
-            z: low..(high + 1),
-        }
-    }
-
-    fn from_instr(instr: &Instr) -> Self {
-        Self {
-            x: instr.x0..(instr.x1 + 1),
-            y: instr.y0..(instr.y1 + 1),
-            z: instr.z0..(instr.z1 + 1),
-        }
-    }
-
-    fn volume(&self) -> Num {
-        (self.x.end - self.x.start) * (self.y.end - self.y.start) * (self.z.end - self.z.start)
-    }
-
-    fn intersection(&self, that: &Self) -> Option<Cube> {
-        let f = |a: &Range<Num>, b: &Range<Num>| {
-            let start = Num::max(a.start, b.start);
-            let end = Num::min(a.end, b.end);
-            (start < end).then(|| start..end)
-        };
-
-        Some(Self {
-            x: f(&self.x, &that.x)?,
-            y: f(&self.y, &that.y)?,
-            z: f(&self.z, &that.z)?,
-        })
+impl Instr {
+    fn region(&self) -> Aabb<3> {
+        Aabb::inclusive([self.x0, self.y0, self.z0], [self.x1, self.y1, self.z1])
     }
+}
 
-    fn intersects(&self, that: &Self) -> bool {
-        self.intersection(that).is_some()
-    }
+fn world(low: Num, high: Num) -> Aabb<3> {
+    Aabb::inclusive([low; 3], [high; 3])
+}
 
-    fn split(&self, that: &Self, out: &mut Vec<Self>) {
-        let p = if let Some(p) = self.intersection(that) {
-            p
-        } else {
-            return out.push(self.clone());
-        };
+fn execute(instructions: &[Instr], world: Aabb<3>) -> usize {
+    // Disjoint boxes of cubes that are on.
+    let mut cubes: Vec<Aabb<3>> = vec![];
 
-        let subranges = |this: &Range<Num>, that: &Range<Num>| {
-            [
-                this.start..that.start,
-                that.start..that.end,
-                that.end..this.end,
-            ]
+    for instr in instructions {
+        let region = match instr.region().intersection(&world) {
+            Some(region) => region,
+            None => continue,
         };
 
-        for x in subranges(&self.x, &p.x) {
-            for y in subranges(&self.y, &p.y) {
-                for z in subranges(&self.z, &p.z) {
-                    if !x.is_empty() && !y.is_empty() && !z.is_empty() {
-                        out.push(Cube {
-                            x: x.clone(),
-                            y: y.clone(),
-                            z: z.clone(),
-                        });
-                    }
-                }
-            }
-        }
-    }
-}
-
-fn execute(instructions: &[Instr], world: Cube) -> usize {
-    let mut cubes = vec![world];
+        cubes = cubes
+            .iter()
+            .flat_map(|cube| cube.subtract(&region))
+            .collect();
 
-    for instr in instructions {
-        for cube in take(&mut cubes) {
-            cube.split(&Cube::from_instr(instr), &mut cubes);
+        if instr.action == "on" {
+            cubes.push(region);
         }
     }
 
-    sum(map(cubes, |cube| {
-        for instr in rev(instructions) {
-            if cube.intersects(&Cube::from_instr(instr)) {
-                return (instr.action == "on") as usize * cube.volume() as usize;
-            }
-        }
-
-        0
-    }))
+    cubes.iter().map(|cube| cube.volume() as usize).sum()
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let instr = parse(lines)?;
 
-    println!("part A: {:?}", execute(&instr, Cube::from_bounds(-50, 50)));
-    println!(
-        "part B: {:?}",
-        execute(&instr, Cube::from_bounds(-500000, 500000))
-    );
+    answer('A', execute(&instr, world(-50, 50)));
+    answer('B', execute(&instr, world(-500000, 500000)));
 
     Ok(())
 }
@@ -176,10 +140,7 @@ mod tests {
             "on x=10..10,y=10..10,z=10..10",
         ];
 
-        assert_eq!(
-            execute(&parse(&lines).unwrap(), Cube::from_bounds(-50, 50)),
-            39
-        );
+        assert_eq!(execute(&parse(&lines).unwrap(), world(-50, 50)), 39);
 
         let lines = [
             "on x=-20..26,y=-36..17,z=-47..7",
@@ -206,10 +167,7 @@ mod tests {
             "on x=967..23432,y=45373..81175,z=27513..53682",
         ];
 
-        assert_eq!(
-            execute(&parse(&lines).unwrap(), Cube::from_bounds(-50, 50)),
-            590784
-        );
+        assert_eq!(execute(&parse(&lines).unwrap(), world(-50, 50)), 590784);
     }
 
     #[test]
@@ -278,8 +236,43 @@ mod tests {
         ];
 
         assert_eq!(
-            execute(&parse(&lines).unwrap(), Cube::from_bounds(-500000, 500000)),
+            execute(&parse(&lines).unwrap(), world(-500000, 500000)),
             2758514936282235
         );
     }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let action = ["on", "off"][rng.random_range(0..2)].to_string();
+            let mut coord = || rng.random_range(-100000..=100000);
+            let instr = Instr {
+                action,
+                x0: coord(),
+                x1: coord(),
+                y0: coord(),
+                y1: coord(),
+                z0: coord(),
+                z1: coord(),
+            };
+            let line = instr.to_string();
+
+            assert_eq!(parse(&[&line]).unwrap(), [instr]);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "on x=10..12,y=10..12,z=10..12",
+                "on x=11..13,y=11..13,z=11..13",
+                "off x=9..11,y=9..11,z=9..11",
+                "on x=10..10,y=10..10,z=10..10",
+            ],
+            run,
+        );
+    }
 }
@@ -1,8 +1,4 @@
 use crate::common::*;
-use binary_heap_plus::BinaryHeap;
-use std::cmp::Reverse;
-use std::collections::hash_map::Entry;
-use std::rc::Rc;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -13,13 +9,16 @@ enum Amphi {
     D,
 }
 
-type State<const N: usize> = Rc<[[Option<Amphi>; 11]; N]>;
+/// The hallway is the first row, the rooms are in the rows below.
+type State<const N: usize> = [[Option<Amphi>; 11]; N];
 
 fn parse<const N: usize>(lines: Lines) -> Result<State<N>>
 where
     [[Option<Amphi>; 11]; N]: Default,
 {
+    ensure!(lines.len() == N + 2, "expecting {} lines", N + 2);
     let mut state = <[[Option<Amphi>; 11]; N]>::default();
+    let mut counts = [0; 4];
 
     for (i, line) in enumerate(lines) {
         for (j, c) in enumerate(line.chars()) {
@@ -31,19 +30,35 @@ where
                 _ => continue,
             };
 
+            let in_hallway = i == 1 && (1..=11).contains(&j);
+            let in_room = (2..=N).contains(&i) && [3, 5, 7, 9].contains(&j);
+            ensure!(
+                in_hallway || in_room,
+                "unexpected amphipod at line {}, column {}",
+                i + 1,
+                j + 1
+            );
+
             state[i - 1][j - 1] = Some(a);
+            counts[a as usize] += 1;
         }
     }
 
-    Ok(Rc::new(state))
+    ensure!(
+        counts == [N - 1; 4],
+        "expecting {} amphipods of each type",
+        N - 1
+    );
+
+    Ok(state)
 }
 
 fn is_solved<const N: usize>(state: &State<N>) -> bool {
     let mut solved = true;
 
-    for i in 1..N {
+    for row in &state[1..] {
         for j in 0..4 {
-            solved &= match state[i][2 * j + 2] {
+            solved &= match row[2 * j + 2] {
                 Some(c) => c as usize == j,
                 None => false,
             };
@@ -53,158 +68,149 @@ fn is_solved<const N: usize>(state: &State<N>) -> bool {
     solved
 }
 
-fn print_state<const N: usize>(state: &State<N>) {
+fn format_state<const N: usize>(state: &State<N>) -> String {
     use Amphi::*;
 
-    for i in 0..N {
-        for j in 0..11 {
-            match state[i][j] {
-                Some(A) => print!("A"),
-                Some(B) => print!("B"),
-                Some(C) => print!("C"),
-                Some(D) => print!("D"),
-                None => print!("."),
-            }
-        }
+    let cell = |i: usize, j: usize| match state[i][j] {
+        Some(A) => 'A',
+        Some(B) => 'B',
+        Some(C) => 'C',
+        Some(D) => 'D',
+        None => '.',
+    };
+
+    let mut lines = vec!["#############".to_string()];
+    lines.push(format!(
+        "#{}#",
+        (0..11).map(|j| cell(0, j)).collect::<String>()
+    ));
 
-        println!();
+    for i in 1..N {
+        let rooms = (0..4).map(|k| cell(i, 2 * k + 2)).join("#");
+
+        lines.push(if i == 1 {
+            format!("###{}###", rooms)
+        } else {
+            format!("  #{}#", rooms)
+        });
     }
 
-    println!();
+    lines.push("  #########".to_string());
+    lines.join("\n")
 }
 
-fn solve<const N: usize>(initial_state: State<N>) -> usize {
-    const HALLWAYS_SPOTS: [usize; 7] = [0, 1, 3, 5, 7, 9, 10];
+fn print_state<const N: usize>(state: &State<N>) {
+    println!("{}", format_state(state));
+    println!();
+}
 
-    let mut previous = HashMap::<State<N>, State<N>>::default();
-    let mut queue =
-        BinaryHeap::<(State<N>, State<N>, usize), _>::new_by_key(|&(_, _, cost)| Reverse(cost));
-    queue.push((initial_state.clone(), initial_state, 0));
-
-    fn foo<const N: usize>(
-        state: &State<N>,
-        cost: usize,
-        src: [usize; 2],
-        dst: [usize; 2],
-    ) -> Option<(State<N>, State<N>, usize)> {
-        let [mut row, mut col] = src;
-        let mut steps = 0;
-
-        while [row, col] != dst {
-            steps += 1;
-
-            if row != 0 && col != dst[1] {
-                row -= 1; // Move up
-            } else if row == 0 && col < dst[1] {
-                col += 1; // Move right
-            } else if row == 0 && col > dst[1] {
-                col -= 1; // Move left
-            } else {
-                row += 1; // Move down
-            }
+/// Moves the amphipod at `src` to `dst`, returns `None` if another amphipod is in the way.
+fn move_amphi<const N: usize>(
+    state: &State<N>,
+    src: [usize; 2],
+    dst: [usize; 2],
+) -> Option<(State<N>, usize)> {
+    let [mut row, mut col] = src;
+    let mut steps = 0;
+
+    while [row, col] != dst {
+        steps += 1;
+
+        if row != 0 && col != dst[1] {
+            row -= 1; // Move up
+        } else if row == 0 && col < dst[1] {
+            col += 1; // Move right
+        } else if row == 0 && col > dst[1] {
+            col -= 1; // Move left
+        } else {
+            row += 1; // Move down
+        }
 
-            if state[row][col].is_some() {
-                return None;
-            }
+        if state[row][col].is_some() {
+            return None;
         }
+    }
 
-        let mut new_state = (**state).clone();
-        let me = new_state[src[0]][src[1]].take().unwrap();
-        new_state[dst[0]][dst[1]] = Some(me);
-        let new_state = Rc::new(new_state);
+    let mut new_state = *state;
+    let me = new_state[src[0]][src[1]].take().unwrap();
+    new_state[dst[0]][dst[1]] = Some(me);
 
-        let cost_per_step = match me {
-            Amphi::A => 1,
-            Amphi::B => 10,
-            Amphi::C => 100,
-            Amphi::D => 1000,
-        };
+    let cost_per_step = match me {
+        Amphi::A => 1,
+        Amphi::B => 10,
+        Amphi::C => 100,
+        Amphi::D => 1000,
+    };
 
-        Some((new_state, state.clone(), cost + cost_per_step * steps))
-    }
+    Some((new_state, cost_per_step * steps))
+}
 
-    while let Some((state, prev, cost)) = queue.pop() {
-        match previous.entry(state.clone()) {
-            Entry::Vacant(e) => e.insert(prev),
-            Entry::Occupied(_) => continue,
-        };
+fn successors<const N: usize>(state: &State<N>) -> Vec<(State<N>, usize)> {
+    const HALLWAYS_SPOTS: [usize; 7] = [0, 1, 3, 5, 7, 9, 10];
+    let mut result = vec![];
 
-        if is_solved(&state) {
-            let mut state = state;
-            let mut stack = vec![];
-            loop {
-                stack.push(state.clone());
+    // Try to move into a room
+    'into: for src in HALLWAYS_SPOTS {
+        let c = match state[0][src] {
+            Some(c) => c,
+            None => continue,
+        };
 
-                let prev = state;
-                state = previous[&prev].clone();
-                if prev == state {
-                    break;
-                }
-            }
+        let col = 2 * (c as usize) + 2;
+        let mut row = N - 1;
 
-            for x in rev(stack) {
-                print_state(&x);
+        loop {
+            match state[row][col] {
+                None => break,
+                Some(x) if x == c && row > 1 => row -= 1,
+                _ => continue 'into,
             }
-
-            return cost;
         }
 
-        let mut moved_into = false;
-
-        // Try to move into a room
-        'into: for src in HALLWAYS_SPOTS {
-            let c = match state[0][src] {
-                Some(c) => c,
-                None => continue,
-            };
-
-            let col = 2 * (c as usize) + 2;
-            let mut row = N - 1;
+        result.extend(move_amphi(state, [0, src], [row, col]));
+    }
 
-            loop {
-                match state[row][col] {
-                    None => break,
-                    Some(x) if x == c && row > 1 => row -= 1,
-                    _ => continue 'into,
+    // Moving into a room is always the best choice, so only otherwise try to move out of a room
+    if result.is_empty() {
+        'out: for i in 0..4 {
+            let col = 2 * i + 2;
+            let mut row = 1;
+
+            while state[row][col].is_none() {
+                if row + 1 < N {
+                    row += 1;
+                } else {
+                    continue 'out;
                 }
             }
 
-            if let Some(x) = foo(&state, cost, [0, src], [row, col]) {
-                moved_into = true;
-                queue.push(x);
+            for target in HALLWAYS_SPOTS {
+                result.extend(move_amphi(state, [row, col], [0, target]));
             }
         }
+    }
 
-        // Try to move out of room
-        if !moved_into {
-            'out: for i in 0..4 {
-                let col = 2 * i + 2;
-                let mut row = 1;
-
-                while state[row][col].is_none() {
-                    if row + 1 < N {
-                        row += 1;
-                    } else {
-                        continue 'out;
-                    }
-                }
+    result
+}
 
-                for target in HALLWAYS_SPOTS {
-                    if let Some(x) = foo(&state, cost, [row, col], [0, target]) {
-                        queue.push(x);
-                    }
-                }
-            }
+fn solve<const N: usize>(initial_state: State<N>) -> Result<usize> {
+    let (cost, path) =
+        dijkstra(initial_state, successors, is_solved).context("no solution found!")?;
+
+    if is_verbose() {
+        for state in &path {
+            print_state(state);
         }
     }
 
-    panic!("no solution found!");
+    Ok(cost)
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let state = parse::<3>(lines)?;
-    println!("part A: {}", solve(state));
+    answer('A', solve(state)?);
 
+    ensure!(lines.len() == 5, "invalid input");
     let state = parse::<5>(&[
         lines[0],
         lines[1],
@@ -214,7 +220,7 @@ pub(crate) fn run(lines: Lines) -> Result {
         lines[3],
         lines[4],
     ])?;
-    println!("part B: {}", solve(state));
+    answer('B', solve(state)?);
 
     Ok(())
 }
@@ -233,7 +239,7 @@ mod tests {
             "  #########",
         ];
 
-        assert_eq!(solve(parse::<3>(&lines).unwrap()), 12521);
+        assert_eq!(solve(parse::<3>(&lines).unwrap()).unwrap(), 12521);
     }
 
     #[test]
@@ -248,6 +254,55 @@ mod tests {
             "  #########",
         ];
 
-        assert_eq!(solve(parse::<5>(&lines).unwrap()), 44169);
+        assert_eq!(solve(parse::<5>(&lines).unwrap()).unwrap(), 44169);
+    }
+
+    #[test]
+    fn test_format() {
+        use rand::seq::SliceRandom;
+
+        fn check<const N: usize>(rng: &mut impl RngExt)
+        where
+            [[Option<Amphi>; 11]; N]: Default,
+        {
+            let mut state = <[[Option<Amphi>; 11]; N]>::default();
+            let mut cells = (0..11).map(|j| [0, j]).collect_vec();
+
+            for i in 1..N {
+                cells.extend([[i, 2], [i, 4], [i, 6], [i, 8]]);
+            }
+
+            cells.shuffle(rng);
+
+            for (k, [i, j]) in enumerate(cells.into_iter().take(4 * (N - 1))) {
+                state[i][j] = Some([Amphi::A, Amphi::B, Amphi::C, Amphi::D][k % 4]);
+            }
+
+            let text = format_state(&state);
+
+            assert_eq!(parse::<N>(&text.lines().collect_vec()).unwrap(), state);
+        }
+
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            check::<3>(&mut rng);
+            check::<5>(&mut rng);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "#############",
+                "#...........#",
+                "###B#A#C#D###",
+                "  #A#B#C#D#",
+                "  #########",
+            ],
+            // Only the small burrow, searching the unfolded one for every input takes too long.
+            |lines| solve(parse::<3>(lines)?).map(drop),
+        );
     }
 }
@@ -1,5 +1,6 @@
 use crate::common::*;
 use std::collections::VecDeque;
+use std::fmt::{self, Display};
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[repr(u8)]
@@ -28,6 +29,43 @@ enum Expr {
     Eq(Var, Arg),
 }
 
+impl Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Var::W => "w",
+            Var::X => "x",
+            Var::Y => "y",
+            Var::Z => "z",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arg::Var(v) => write!(f, "{}", v),
+            Arg::Const(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Expr::*;
+
+        match self {
+            Input(a) => write!(f, "inp {}", a),
+            Add(a, b) => write!(f, "add {} {}", a, b),
+            Mul(a, b) => write!(f, "mul {} {}", a, b),
+            Div(a, b) => write!(f, "div {} {}", a, b),
+            Mod(a, b) => write!(f, "mod {} {}", a, b),
+            Eq(a, b) => write!(f, "eql {} {}", a, b),
+        }
+    }
+}
+
 fn parse_expr(line: &str) -> Result<Expr> {
     use Expr::*;
 
@@ -118,7 +156,7 @@ fn reorder_instructions(instr: &[Expr]) -> Vec<Expr> {
     lines.into()
 }
 
-fn execute<A>(instr: &[Expr], arbiter: A) -> Num
+fn execute<A>(instr: &[Expr], arbiter: A) -> Result<Num>
 where
     A: Fn(Num, Num) -> Num,
 {
@@ -127,13 +165,16 @@ where
     fn evolve<'a>(
         instr: &'a [Expr],
         iter: impl IntoIterator<Item = (State, Num)> + 'a,
-    ) -> impl Iterator<Item = (State, Num)> + 'a {
+    ) -> impl Iterator<Item = Result<(State, Num)>> + 'a {
         iter.into_iter()
             .cartesian_product(1..=9)
             .map(|((state, num), i)| {
-                let new_state = eval(instr, &[i], state);
-                let new_num = 10 * num + i;
-                (new_state, new_num)
+                let new_state = eval(instr, &[i], state)?;
+                let new_num = num
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(i))
+                    .context("model number overflows")?;
+                Ok((new_state, new_num))
             })
     }
 
@@ -144,7 +185,7 @@ where
 
     let mut new_states = HashMap::default();
     let mut states = HashMap::default();
-    let initial_state = eval(prelude, &[], default());
+    let initial_state = eval(prelude, &[], default())?;
     states.insert(initial_state, 0);
 
     while let Some(split) = instr.iter().skip(1).position(|e| matches!(e, Input(_))) {
@@ -153,7 +194,9 @@ where
 
         new_states.clear();
 
-        for (state, num) in evolve(head, states.drain()) {
+        for result in evolve(head, states.drain()) {
+            let (state, num) = result?;
+
             new_states
                 .entry(state)
                 .and_modify(|v| *v = arbiter(*v, num))
@@ -163,19 +206,26 @@ where
         swap(&mut states, &mut new_states);
     }
 
-    evolve(instr, states.drain())
-        .filter_map(|(state, num)| (state[Var::Z as usize] == 0).then(|| num))
-        .reduce(arbiter)
-        .unwrap()
+    let mut best = None;
+
+    for result in evolve(instr, states.drain()) {
+        let (state, num) = result?;
+
+        if state[Var::Z as usize] == 0 {
+            best = Some(best.map_or(num, |v| arbiter(v, num)));
+        }
+    }
+
+    best.context("no valid model number found")
 }
 
 #[inline(always)]
-fn eval(lines: &[Expr], mut inputs: &[Num], mut state: State) -> State {
+fn eval(lines: &[Expr], mut inputs: &[Num], mut state: State) -> Result<State> {
     use Expr::*;
 
-    fn evolve<F>(out: Var, arg: Arg, fun: F, state: &mut State)
+    fn evolve<F>(out: Var, arg: Arg, fun: F, state: &mut State) -> Option<()>
     where
-        F: Fn(Num, Num) -> Num,
+        F: Fn(Num, Num) -> Option<Num>,
     {
         let lhs = state[out as usize];
         let rhs = match arg {
@@ -183,33 +233,44 @@ fn eval(lines: &[Expr], mut inputs: &[Num], mut state: State) -> State {
             Arg::Const(c) => c,
         };
 
-        state[out as usize] = fun(lhs, rhs);
+        state[out as usize] = fun(lhs, rhs)?;
+        Some(())
     }
 
     for &line in lines {
-        match line {
+        let valid = match line {
             Input(v) => {
-                let (&first, rest) = inputs.split_first().unwrap();
+                let (&first, rest) = inputs.split_first().context("missing input")?;
                 state[v as usize] = first;
                 inputs = rest;
+                Some(())
             }
-            Add(a, b) => evolve(a, b, |x, y| x + y, &mut state),
-            Mul(a, b) => evolve(a, b, |x, y| x * y, &mut state),
-            Div(a, b) => evolve(a, b, |x, y| x / y, &mut state),
-            Mod(a, b) => evolve(a, b, |x, y| x % y, &mut state),
-            Eq(a, b) => evolve(a, b, |x, y| (x == y) as _, &mut state),
+            Add(a, b) => evolve(a, b, Num::checked_add, &mut state),
+            Mul(a, b) => evolve(a, b, Num::checked_mul, &mut state),
+            Div(a, b) => evolve(a, b, Num::checked_div, &mut state),
+            Mod(a, b) => evolve(
+                a,
+                b,
+                |x, y| x.checked_rem(y).filter(|_| x >= 0 && y > 0),
+                &mut state,
+            ),
+            Eq(a, b) => evolve(a, b, |x, y| Some((x == y) as _), &mut state),
+        };
+
+        if valid.is_none() {
+            bail!("invalid operation: {}, with state {:?}", line, state);
         }
     }
 
-    state
+    Ok(state)
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let lines = parse(lines)?;
     let lines = reorder_instructions(&lines);
 
-    println!("part A: {:?}", execute(&lines, |a, b| Num::max(a, b)));
-    println!("part B: {:?}", execute(&lines, |a, b| Num::min(a, b)));
+    answer('A', execute(&lines, |a, b| Num::max(a, b))?);
+    answer('B', execute(&lines, |a, b| Num::min(a, b))?);
     Ok(())
 }
 
@@ -226,4 +287,53 @@ mod tests {
     fn test_b() {
         //
     }
+
+    #[test]
+    fn test_eval() {
+        let program = parse(&["inp x", "mul x -1", "inp y", "add y 2", "mul x y"]).unwrap();
+        assert_eq!(eval(&program, &[3, 4], default()).unwrap(), [0, -18, 6, 0]);
+
+        assert!(eval(&program, &[3], default()).is_err());
+        assert!(eval(&parse(&["div x 0"]).unwrap(), &[], default()).is_err());
+        assert!(eval(&parse(&["add x -1", "mod x 2"]).unwrap(), &[], default()).is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        use Expr::*;
+        const VARS: [Var; 4] = [Var::W, Var::X, Var::Y, Var::Z];
+
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let lhs = VARS[rng.random_range(0..4)];
+            let rhs = if rng.random() {
+                Arg::Var(VARS[rng.random_range(0..4)])
+            } else {
+                Arg::Const(rng.random_range(-100..100))
+            };
+
+            let expr = match rng.random_range(0..6) {
+                0 => Input(lhs),
+                1 => Add(lhs, rhs),
+                2 => Mul(lhs, rhs),
+                3 => Div(lhs, rhs),
+                4 => Mod(lhs, rhs),
+                _ => Eq(lhs, rhs),
+            };
+
+            assert_eq!(parse_expr(&expr.to_string()).unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "inp w", "add z w", "mod z 2", "div w 2", "add y w", "mod y 2", "div w 2",
+                "add x w", "mod x 2", "div w 2", "mod w 2",
+            ],
+            run,
+        );
+    }
 }
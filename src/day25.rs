@@ -1,65 +1,73 @@
 use crate::common::*;
-use ndarray::{Array2, ArrayView2};
 
-fn parse(lines: Lines) -> Result<Array2<char>> {
-    let height = lines.len();
-    let width = lines[0].len();
-    let mut map = Array2::from_elem((height, width), '.');
+fn parse(lines: Lines) -> Result<Grid<char>> {
+    Grid::from_lines(lines, |c| {
+        ensure!(matches!(c, '.' | '>' | 'v'), "invalid character: {:?}", c);
+        Ok(c)
+    })
+}
+
+#[allow(dead_code)]
+fn format(map: &Grid<char>) -> String {
+    map.to_string()
+}
 
-    for (i, line) in enumerate(lines) {
-        ensure!(line.len() == width, "invalid input");
+/// One step in which first the east-facing herd moves and then the south-facing herd. The sea
+/// cucumbers reappear on the other side when moving off the edge of the map.
+fn evolve(input: &Grid<char>) -> Grid<char> {
+    let mut automaton = Automaton::new(input.clone(), Boundary::Toroidal);
 
-        for (j, c) in enumerate(line.chars()) {
-            map[[i, j]] = c;
+    automaton.step_phases(2, |phase, cell| {
+        let (herd, [di, dj]) = [('>', [0, 1]), ('v', [1, 0])][phase];
+
+        match *cell.center() {
+            '.' if cell[[-di, -dj]] == herd => herd,
+            c if c == herd && cell[[di, dj]] == '.' => '.',
+            c => c,
         }
-    }
+    });
 
-    Ok(map)
+    automaton.into_grid()
 }
 
-fn evolve(input: ArrayView2<char>) -> Array2<char> {
-    let (height, width) = input.dim();
-    let mut output = input.to_owned();
-
-    for i in 0..height {
-        for j in 0..width {
-            if input[[i, j]] == '>' && input[[i, (j + 1) % width]] == '.' {
-                output[[i, j]] = '.';
-                output[[i, (j + 1) % width]] = '>';
-            }
-        }
-    }
+fn evolve_forever(map: Grid<char>) -> Result<usize> {
+    let cycle = find_cycle(map, evolve);
 
-    let input = output.clone();
+    // If the herd returns to an earlier state without coming to a halt, it keeps moving forever.
+    ensure!(cycle.is_fixpoint(), "sea cucumbers never stop moving");
 
-    for i in 0..height {
-        for j in 0..width {
-            if input[[i, j]] == 'v' && input[[(i + 1) % height, j]] == '.' {
-                output[[i, j]] = '.';
-                output[[(i + 1) % height, j]] = 'v';
-            }
-        }
-    }
+    Ok(cycle.start() + 1)
+}
 
-    output
+fn to_image(map: &Grid<char>) -> Image {
+    Image::from_fn(map.ncols(), map.nrows(), |x, y| match map[[y, x]] {
+        '>' => [255, 140, 0],
+        'v' => [30, 144, 255],
+        _ => BLACK,
+    })
 }
 
-fn evolve_forever(mut current: Array2<char>) -> usize {
-    for steps in 1.. {
-        let prev = current;
-        current = evolve(prev.view());
+fn render(mut map: Grid<char>, steps: usize) -> Result {
+    let mut frames = Frames::new("day25")?;
+
+    if frames.is_enabled() {
+        frames.push(&to_image(&map))?;
 
-        if current == prev {
-            return steps;
+        for _ in 0..steps {
+            map = evolve(&map);
+            frames.push(&to_image(&map))?;
         }
     }
 
-    unreachable!()
+    Ok(())
 }
 
 pub(crate) fn run(lines: Lines) -> Result {
     let map = parse(lines)?;
-    println!("part A: {}", evolve_forever(map));
+
+    let steps = evolve_forever(map.clone())?;
+    answer('A', steps);
+    render(map, steps)?;
 
     Ok(())
 }
@@ -83,11 +91,53 @@ mod tests {
         ];
 
         let map = parse(&lines).unwrap();
-        assert_eq!(evolve_forever(map), 58);
+        assert_eq!(evolve_forever(map.clone()).unwrap(), 58);
+        assert_eq!(
+            state_after(map.clone(), 1_000_000_000_000, evolve),
+            state_after(map, 57, evolve)
+        );
+
+        let map = parse(&[">.", ".."]).unwrap();
+        assert!(evolve_forever(map.clone()).is_err());
+        assert_eq!(
+            state_after(map.clone(), 1_000_000_000_001, evolve),
+            evolve(&map)
+        );
     }
 
     #[test]
     fn test_b() {
         //
     }
+
+    #[test]
+    fn test_format() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let (n, m) = (rng.random_range(1..20), rng.random_range(1..20));
+            let map = Grid::from_fn(n, m, |_| ['.', '>', 'v'][rng.random_range(0..3)]);
+            let text = format(&map);
+
+            assert_eq!(parse(&text.lines().collect_vec()).unwrap(), map);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(
+            &[
+                "v...>>.vv>",
+                ".vv>>.vv..",
+                ">>.>v>...v",
+                ">>v>>.>.v.",
+                "v>v.vv.v..",
+                ">.>>..v...",
+                ".vv..>.>v.",
+                "v.v..>>v.v",
+                "....v..v.>",
+            ],
+            run,
+        );
+    }
 }
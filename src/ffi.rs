@@ -0,0 +1,281 @@
+//! C interface to the solvers, declared for C and C++ in `include/aoc.h`.
+//!
+//! The header is generated from this file by [`header`], run the tests with `UPDATE_HEADER=1` to
+//! rewrite it after changing the interface.
+use crate::common::*;
+use crate::{solve, DAYS};
+use std::os::raw::{c_char, c_int};
+use std::panic::catch_unwind;
+use std::ptr;
+use std::slice;
+
+pub const AOC_OK: c_int = 0;
+pub const AOC_INVALID_ARGUMENT: c_int = 1;
+pub const AOC_INVALID_INPUT: c_int = 2;
+pub const AOC_SOLVE_FAILED: c_int = 3;
+pub const AOC_BUFFER_TOO_SMALL: c_int = 4;
+pub const AOC_PANIC: c_int = 5;
+
+/// Status codes with their names in the header and their descriptions, NUL-terminated for C.
+const STATUSES: [(&str, c_int, &str); 6] = [
+    ("AOC_OK", AOC_OK, "success\0"),
+    (
+        "AOC_INVALID_ARGUMENT",
+        AOC_INVALID_ARGUMENT,
+        "invalid day, part or pointer\0",
+    ),
+    (
+        "AOC_INVALID_INPUT",
+        AOC_INVALID_INPUT,
+        "input is not valid UTF-8\0",
+    ),
+    (
+        "AOC_SOLVE_FAILED",
+        AOC_SOLVE_FAILED,
+        "input could not be solved\0",
+    ),
+    (
+        "AOC_BUFFER_TOO_SMALL",
+        AOC_BUFFER_TOO_SMALL,
+        "output buffer is too small\0",
+    ),
+    ("AOC_PANIC", AOC_PANIC, "solver panicked\0"),
+];
+
+/// Turns off printing on the current thread until dropped, also when unwinding from a panic.
+struct Quiet {
+    verbose: bool,
+}
+
+impl Quiet {
+    fn new() -> Self {
+        let verbose = is_verbose();
+        set_verbose(false);
+        Quiet { verbose }
+    }
+}
+
+impl Drop for Quiet {
+    fn drop(&mut self) {
+        set_verbose(self.verbose);
+    }
+}
+
+/// Solves one part of a puzzle, see `include/aoc.h` for the full description.
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes, `out_len` must point to a `size_t` holding the
+/// capacity of `out_buf` and `out_buf` must point to that many writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_solve(
+    day: u32,
+    part: u32,
+    input: *const u8,
+    len: usize,
+    out_buf: *mut c_char,
+    out_len: *mut usize,
+) -> c_int {
+    if (input.is_null() && len > 0) || out_len.is_null() || (out_buf.is_null() && *out_len > 0) {
+        return AOC_INVALID_ARGUMENT;
+    }
+
+    if !(1..=DAYS.len()).contains(&(day as usize)) {
+        return AOC_INVALID_ARGUMENT;
+    }
+
+    let part = match part {
+        1 => 'A',
+        2 => 'B',
+        _ => return AOC_INVALID_ARGUMENT,
+    };
+
+    let input = if len > 0 {
+        slice::from_raw_parts(input, len)
+    } else {
+        &[]
+    };
+
+    let input = match std::str::from_utf8(input) {
+        Ok(input) => input,
+        Err(_) => return AOC_INVALID_INPUT,
+    };
+
+    // Unwinding into C is undefined behavior, so panics are caught and reported as a status.
+    let result = catch_unwind(|| {
+        let _quiet = Quiet::new();
+        solve(day as usize, input)
+    });
+
+    let answers = match result {
+        Ok(Ok(answers)) => answers,
+        Ok(Err(_)) => return AOC_SOLVE_FAILED,
+        Err(_) => return AOC_PANIC,
+    };
+
+    let answer = match find(answers, |(p, _)| *p == part) {
+        Some((_, answer)) => answer,
+        None => return AOC_INVALID_ARGUMENT,
+    };
+
+    let capacity = *out_len;
+    *out_len = answer.len();
+
+    if answer.len() >= capacity {
+        return AOC_BUFFER_TOO_SMALL;
+    }
+
+    ptr::copy_nonoverlapping(answer.as_ptr(), out_buf as *mut u8, answer.len());
+    *out_buf.add(answer.len()) = 0;
+    AOC_OK
+}
+
+/// Returns a static, NUL-terminated description of the given status code.
+#[no_mangle]
+pub extern "C" fn aoc_status_message(status: c_int) -> *const c_char {
+    let message = match find(STATUSES, |&(_, code, _)| code == status) {
+        Some((_, _, message)) => message,
+        None => "unknown status\0",
+    };
+
+    message.as_ptr() as *const c_char
+}
+
+/// Returns the contents of `include/aoc.h`.
+pub fn header() -> String {
+    let mut lines = vec![
+        "/* Generated from src/ffi.rs, do not edit by hand. */".to_string(),
+        "#ifndef AOC_H".to_string(),
+        "#define AOC_H".to_string(),
+        "".to_string(),
+        "#include <stddef.h>".to_string(),
+        "#include <stdint.h>".to_string(),
+        "".to_string(),
+        "#ifdef __cplusplus".to_string(),
+        "extern \"C\" {".to_string(),
+        "#endif".to_string(),
+        "".to_string(),
+    ];
+
+    for (name, code, message) in STATUSES {
+        let message = message.trim_end_matches('\0');
+        lines.push(format!("#define {} {} /* {} */", name, code, message));
+    }
+
+    lines.extend(
+        [
+            "",
+            "/*",
+            " * Solves part `part` (1 or 2) of day `day` (1 to 25) for the `len` bytes of",
+            " * UTF-8 text at `input`. Both parts are solved on every call.",
+            " *",
+            " * `*out_len` must hold the capacity of `out_buf` in bytes. On success the answer",
+            " * is written to `out_buf` as a NUL-terminated string. On success and on",
+            " * AOC_BUFFER_TOO_SMALL, `*out_len` is set to the length of the answer without",
+            " * the NUL terminator.",
+            " */",
+            "int aoc_solve(uint32_t day, uint32_t part, const uint8_t *input, size_t len,",
+            "              char *out_buf, size_t *out_len);",
+            "",
+            "/* Returns a static description of a status code returned by aoc_solve. */",
+            "const char *aoc_status_message(int status);",
+            "",
+            "#ifdef __cplusplus",
+            "}",
+            "#endif",
+            "",
+            "#endif /* AOC_H */",
+        ]
+        .map(str::to_string),
+    );
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    const INPUT: &str = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+
+    fn call(day: u32, part: u32, input: &[u8], capacity: usize) -> (c_int, usize, String) {
+        let mut buffer = vec![1 as c_char; capacity];
+        let mut len = capacity;
+        let status = unsafe {
+            aoc_solve(
+                day,
+                part,
+                input.as_ptr(),
+                input.len(),
+                buffer.as_mut_ptr(),
+                &mut len,
+            )
+        };
+
+        let answer = if status == AOC_OK {
+            let answer = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+            answer.to_str().unwrap().to_string()
+        } else {
+            String::new()
+        };
+
+        (status, len, answer)
+    }
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(call(1, 1, INPUT.as_bytes(), 64), (AOC_OK, 1, "7".into()));
+        assert_eq!(call(1, 2, INPUT.as_bytes(), 64), (AOC_OK, 1, "5".into()));
+        assert_eq!(call(1, 1, INPUT.as_bytes(), 2), (AOC_OK, 1, "7".into()));
+        assert_eq!(call(1, 1, INPUT.as_bytes(), 1).0, AOC_BUFFER_TOO_SMALL);
+        assert_eq!(call(1, 1, INPUT.as_bytes(), 1).1, 1);
+
+        assert_eq!(call(0, 1, INPUT.as_bytes(), 64).0, AOC_INVALID_ARGUMENT);
+        assert_eq!(call(26, 1, INPUT.as_bytes(), 64).0, AOC_INVALID_ARGUMENT);
+        assert_eq!(call(1, 3, INPUT.as_bytes(), 64).0, AOC_INVALID_ARGUMENT);
+        assert_eq!(call(25, 2, b"..\n..", 64).0, AOC_INVALID_ARGUMENT);
+        assert_eq!(call(1, 1, b"1\nx\n3", 64).0, AOC_SOLVE_FAILED);
+        assert_eq!(call(1, 1, b"1\n\xff\n3", 64).0, AOC_INVALID_INPUT);
+
+        let mut len = 0;
+        let status = unsafe { aoc_solve(1, 1, ptr::null(), 0, ptr::null_mut(), &mut len) };
+        assert_eq!(status, AOC_SOLVE_FAILED);
+
+        let status = unsafe { aoc_solve(1, 1, ptr::null(), 5, ptr::null_mut(), &mut len) };
+        assert_eq!(status, AOC_INVALID_ARGUMENT);
+
+        let message = unsafe { CStr::from_ptr(aoc_status_message(AOC_BUFFER_TOO_SMALL)) };
+        assert_eq!(message.to_str().unwrap(), "output buffer is too small");
+    }
+
+    #[test]
+    fn test_quiet() {
+        assert!(is_verbose());
+
+        let result = catch_unwind(|| {
+            let _quiet = Quiet::new();
+            assert!(!is_verbose());
+            panic!("solver failed");
+        });
+
+        assert!(result.is_err());
+        assert!(is_verbose());
+    }
+
+    #[test]
+    fn test_header() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/include/aoc.h");
+
+        if std::env::var_os("UPDATE_HEADER").is_some() {
+            std::fs::write(path, header()).unwrap();
+        }
+
+        let current = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            current == header(),
+            "{} is out of date, run the tests with UPDATE_HEADER=1",
+            path
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Journal of answers that were submitted to the website, together with the feedback received.
+//!
+//! The journal is a plain text file with one submission per line, for example:
+//!
+//! ```text
+//! # day part answer outcome
+//! 5 B 19374 too high
+//! 5 B 19230 correct
+//! ```
+//!
+//! Empty lines and lines starting with `#` are ignored.
+use crate::common::*;
+use std::fmt::{self, Display};
+use std::fs::{read_to_string, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Correct,
+    TooHigh,
+    TooLow,
+    Wrong,
+}
+
+impl FromStr for Outcome {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match &*s.to_lowercase().replace(['-', '_'], " ") {
+            "correct" => Outcome::Correct,
+            "too high" => Outcome::TooHigh,
+            "too low" => Outcome::TooLow,
+            "wrong" => Outcome::Wrong,
+            _ => bail!(
+                "unknown outcome {:?}, expecting correct, too high, too low or wrong",
+                s
+            ),
+        })
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Correct => "correct",
+            Outcome::TooHigh => "too high",
+            Outcome::TooLow => "too low",
+            Outcome::Wrong => "wrong",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub day: usize,
+    pub part: char,
+    pub answer: String,
+    pub outcome: Outcome,
+}
+
+impl FromStr for Entry {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let mut words = line.split_whitespace();
+        let (day, part, answer) = match (words.next(), words.next(), words.next()) {
+            (Some(day), Some(part), Some(answer)) => (day, part, answer),
+            _ => bail!("expecting day, part, answer and outcome"),
+        };
+
+        Ok(Entry {
+            day: day
+                .parse()
+                .with_context(|| format!("invalid day: {:?}", day))?,
+            part: parse_part(part)?,
+            answer: answer.to_string(),
+            outcome: words.join(" ").parse()?,
+        })
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.day, self.part, self.answer, self.outcome
+        )
+    }
+}
+
+pub fn parse_part(part: &str) -> Result<char> {
+    match part {
+        "A" | "a" => Ok('A'),
+        "B" | "b" => Ok('B'),
+        _ => bail!("part must be either A or B, not {:?}", part),
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<Entry>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let content = read_to_string(path)?;
+    let mut entries = vec![];
+
+    for (i, line) in enumerate(content.lines()) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let entry = line
+            .parse()
+            .with_context(|| format!("{}:{}: invalid entry {:?}", path.display(), i + 1, line))?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+pub fn record(path: &Path, entry: &Entry) -> Result {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open: {}", path.display()))?;
+
+    writeln!(file, "{}", entry)?;
+    Ok(())
+}
+
+/// Returns the answer that was accepted for the given day and part, if any.
+pub fn correct_answer(entries: &[Entry], day: usize, part: char) -> Option<&str> {
+    entries
+        .iter()
+        .find(|e| e.day == day && e.part == part && e.outcome == Outcome::Correct)
+        .map(|e| &*e.answer)
+}
+
+/// Returns warnings for `answer` based on the earlier submissions for the same day and part.
+pub fn check(entries: &[Entry], day: usize, part: char, answer: &str) -> Vec<String> {
+    let mut warnings = vec![];
+    let value = answer.parse::<i128>().ok();
+
+    // Renderings are read by eye, they cannot be compared against the submitted text.
+    if answer.contains('\n') {
+        return warnings;
+    }
+
+    for entry in entries {
+        if entry.day != day || entry.part != part {
+            continue;
+        }
+
+        let bound = entry.answer.parse::<i128>().ok();
+        let rejected = match (entry.outcome, value, bound) {
+            (Outcome::Correct, _, _) => {
+                if entry.answer != answer {
+                    warnings.push(format!(
+                        "answer {} differs from the correct answer {}",
+                        answer, entry.answer
+                    ));
+                }
+
+                continue;
+            }
+            _ if entry.answer == answer => true,
+            (Outcome::TooHigh, Some(x), Some(y)) => x >= y,
+            (Outcome::TooLow, Some(x), Some(y)) => x <= y,
+            _ => false,
+        };
+
+        if rejected {
+            warnings.push(format!(
+                "answer {} is rejected by earlier submission {} ({})",
+                answer, entry.answer, entry.outcome
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let entries = map(
+            [
+                "5 B 200 too high",
+                "5 b 100 too-low",
+                "5 B 150 wrong",
+                "6 A 7 correct",
+            ],
+            |line| line.parse::<Entry>().unwrap(),
+        )
+        .collect_vec();
+
+        assert_eq!(entries[1].to_string(), "5 B 100 too low");
+
+        assert!(check(&entries, 5, 'B', "120").is_empty());
+        assert!(check(&entries, 5, 'A', "300").is_empty());
+        assert_eq!(check(&entries, 5, 'B', "150").len(), 1);
+        assert_eq!(check(&entries, 5, 'B', "250").len(), 1);
+        assert_eq!(check(&entries, 5, 'B', "100").len(), 1);
+        assert_eq!(check(&entries, 6, 'A', "8").len(), 1);
+        assert!(check(&entries, 6, 'A', "7").is_empty());
+
+        assert!("5 C 1 wrong".parse::<Entry>().is_err());
+        assert!("5 A 1 too big".parse::<Entry>().is_err());
+    }
+}
@@ -0,0 +1,104 @@
+pub mod common;
+mod day01;
+mod day02;
+mod day03;
+mod day04;
+mod day05;
+mod day06;
+mod day07;
+mod day08;
+mod day09;
+mod day10;
+mod day11;
+mod day12;
+mod day13;
+mod day14;
+mod day15;
+mod day16;
+mod day17;
+mod day18;
+mod day19;
+mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
+mod day25;
+pub mod ffi;
+
+use common::*;
+use std::io::BufRead;
+
+/// Solvers for every day, each one prints and records its answers.
+pub static DAYS: [fn(Lines) -> Result; 25] = [
+    day01::run,
+    day02::run,
+    day03::run,
+    day04::run,
+    day05::run,
+    day06::run,
+    day07::run,
+    day08::run,
+    day09::run,
+    day10::run,
+    day11::run,
+    day12::run,
+    day13::run,
+    day14::run,
+    day15::run,
+    day16::run,
+    day17::run,
+    day18::run,
+    day19::run,
+    day20::run,
+    day21::run,
+    day22::run,
+    day23::run,
+    day24::run,
+    day25::run,
+];
+
+/// Runs the given day on `input` and returns the answers it found.
+pub fn solve(day: usize, input: &str) -> Result<Vec<(char, String)>> {
+    ensure!(
+        (1..=DAYS.len()).contains(&day),
+        "day must be a number between 1 and {}",
+        DAYS.len()
+    );
+
+    let lines = input.trim().split('\n').collect_vec();
+
+    take_answers();
+    (DAYS[day - 1])(&lines)?;
+
+    Ok(take_answers())
+}
+
+/// Runs the given day on input read from `reader`, like [`solve`]. Day 1 processes its input line
+/// by line, so arbitrarily long inputs can be streamed. The other days read all input first.
+pub fn solve_reader<R: BufRead>(day: usize, mut reader: R) -> Result<Vec<(char, String)>> {
+    if day != 1 {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        return solve(day, &input);
+    }
+
+    take_answers();
+    day01::run_reader(reader)?;
+
+    Ok(take_answers())
+}
+
+/// Runs day 2 with the submarine movement model called `model`, such as `"simple"` for part A
+/// or `"aim"` for part B, and returns the final horizontal position times the final depth.
+pub fn solve_submarine(model: &str, input: &str) -> Result<i64> {
+    let lines = input.trim().split('\n').collect_vec();
+    day02::run_model(model, &lines)
+}
+
+/// Counts how often the sum of a window of `width` depth readings of day 1 is larger than the sum
+/// of the window `lag` readings earlier. The readings are processed line by line, like in
+/// [`solve_reader`].
+pub fn count_depth_increases<R: BufRead>(reader: R, width: usize, lag: usize) -> Result<usize> {
+    day01::run_window(reader, width, lag)
+}
@@ -1,91 +1,195 @@
-mod common;
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
-
-use common::*;
+mod journal;
+mod report;
+
+use rust_advent_of_code_2021::common::{self, *};
+use rust_advent_of_code_2021::{count_depth_increases, solve, solve_reader, solve_submarine, DAYS};
 use std::env;
-use std::fs::read_to_string;
-use std::path::Path;
+use std::fs::{read_to_string, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
 fn main() -> Result {
-    let funs = [
-        day01::run,
-        day02::run,
-        day03::run,
-        day04::run,
-        day05::run,
-        day06::run,
-        day07::run,
-        day08::run,
-        day09::run,
-        day10::run,
-        day11::run,
-        day12::run,
-        day13::run,
-        day14::run,
-        day15::run,
-        day16::run,
-        day17::run,
-        day18::run,
-        day19::run,
-        day20::run,
-        day21::run,
-        day22::run,
-        day23::run,
-        day24::run,
-        day25::run,
-    ];
-
-    let mut args = env::args();
-    let binary = args.next().unwrap_or_default();
+    let mut args = env::args().collect_vec();
+    let binary = args.remove(0);
+
+    if let Some(dir) = take_option(&mut args, "--render", "directory")? {
+        set_output_dir(dir);
+    }
+
+    let model = take_option(&mut args, "--model", "name")?;
+    let window = take_option(&mut args, "--window", "width")?;
+
+    if args.first().map(String::as_str) == Some("report") {
+        return run_report(&args[1..]);
+    }
+
+    let mut args = args.into_iter();
     let day = args.next().unwrap_or_default();
 
     let day = if let Ok(i) = day.parse::<usize>() {
         i
     } else {
-        bail!("usage: {} [day]", binary);
+        bail!(
+            "usage: {} [day] [-] [--render directory] [--model name] [--window width[,lag]]\n       {} report [--out report.md] [days]",
+            binary,
+            binary
+        );
     };
 
-    if day == 0 || day > funs.len() {
-        bail!("day must be a number between 1 and {}", funs.len());
+    if day == 0 || day > DAYS.len() {
+        bail!("day must be a number between 1 and {}", DAYS.len());
     }
 
-    let mut input_file = String::new();
+    if let Some(model) = model {
+        ensure!(day == 2, "--model only applies to day 2");
+
+        let mut content = String::new();
+        open_input(day, args.as_slice())?
+            .with_context(|| format!("usage: {} 2 [-] --model name", binary))?
+            .read_to_string(&mut content)?;
+
+        println!("{}: {}", model, solve_submarine(&model, &content)?);
+        return Ok(());
+    }
+
+    if let Some(window) = window {
+        ensure!(day == 1, "--window only applies to day 1");
+
+        let (width, lag) = window.split_once(',').unwrap_or((&window, "1"));
+        let (width, lag) = (width.parse()?, lag.parse()?);
+        let input = open_input(day, args.as_slice())?
+            .with_context(|| format!("usage: {} 1 [-] --window width[,lag]", binary))?;
+
+        let count = count_depth_increases(input, width, lag)?;
+        println!("window {},{}: {}", width, lag, count);
+        return Ok(());
+    }
+
+    // Input from stdin is not the puzzle input, so the answers are not checked against the journal.
+    if args.as_slice() == ["-"] {
+        solve_reader(day, io::stdin().lock())?;
+        return Ok(());
+    }
+
+    let input_file = find_input(day);
+
+    // The journal lives next to the puzzle inputs.
+    let journal_file = input_file.with_file_name("answers");
+
+    if let Some(command) = args.next() {
+        ensure!(
+            command == "record",
+            "usage: {} [day] record [part] [answer] [outcome]",
+            binary
+        );
+
+        let entry = journal::Entry {
+            day,
+            part: journal::parse_part(&args.next().unwrap_or_default())?,
+            answer: args.next().context("missing answer")?,
+            outcome: args.join(" ").parse()?,
+        };
+
+        journal::record(&journal_file, &entry)?;
+        println!("recorded in {}: {}", journal_file.display(), entry);
+        return Ok(());
+    }
+
+    let answers = solve_day(day)?;
+    let entries = journal::load(&journal_file)?;
+
+    for (part, answer) in answers {
+        for warning in journal::check(&entries, day, part, &answer) {
+            eprintln!("warning: part {}: {}", part, warning);
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes `name` and the value after it from the arguments, and returns the value.
+fn take_option(args: &mut Vec<String>, name: &str, value: &str) -> Result<Option<String>> {
+    let index = match args.iter().position(|arg| arg == name) {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+
+    ensure!(index + 1 < args.len(), "missing {} after {}", value, name);
+    let value = args.remove(index + 1);
+    args.remove(index);
+
+    Ok(Some(value))
+}
+
+/// Opens the puzzle input of `day`, or stdin if the only argument is `-`. Returns `None` if there
+/// are other arguments.
+fn open_input(day: usize, args: &[String]) -> Result<Option<Box<dyn BufRead>>> {
+    match args {
+        [] => {
+            let input_file = find_input(day);
+            let file = File::open(&input_file)
+                .with_context(|| format!("failed to open: {}", input_file.display()))?;
+
+            Ok(Some(Box::new(BufReader::new(file))))
+        }
+        [stdin] if stdin == "-" => Ok(Some(Box::new(io::stdin().lock()))),
+        _ => Ok(None),
+    }
+}
+
+/// Runs the given day on its input and returns the answers it found.
+fn solve_day(day: usize) -> Result<Vec<(char, String)>> {
+    let input_file = find_input(day);
+    let content = read_to_string(&input_file)
+        .with_context(|| format!("failed to open: {}", input_file.display()))?;
+
+    solve(day, &content)
+}
+
+fn run_report(args: &[String]) -> Result {
+    let mut out = PathBuf::from("report.md");
+    let mut days = vec![];
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--out" {
+            out = args.next().context("missing file after --out")?.into();
+        } else {
+            let day = arg
+                .parse()
+                .with_context(|| format!("invalid day: {:?}", arg))?;
+            ensure!((1..=DAYS.len()).contains(&day), "invalid day: {}", day);
+            days.push(day);
+        }
+    }
+
+    if days.is_empty() {
+        days = (1..=DAYS.len()).collect();
+    }
+
+    let rows = map(days, |day| {
+        println!("day {}", day);
+        report::measure(day, || solve_day(day))
+    })
+    .collect_vec();
+
+    let entries = journal::load(&find_input(1).with_file_name("answers"))?;
+    report::save(&out, &rows, &entries)?;
+    println!("report written to {}", out.display());
+
+    Ok(())
+}
+
+fn find_input(day: usize) -> PathBuf {
+    let mut input_file = PathBuf::new();
 
     for &prefix in &[".", "..", "inputs", "../inputs/"] {
-        input_file = format!("{}/day{:02}", prefix, day);
+        input_file = Path::new(prefix).join(format!("day{:02}", day));
 
-        if Path::new(&input_file).exists() {
+        if input_file.exists() {
             break;
         }
     }
 
-    let content =
-        read_to_string(&input_file).with_context(|| format!("failed to open: {}", input_file))?;
-    let lines = content.trim().split('\n').collect::<Vec<_>>();
-
-    (funs[day - 1])(&lines)
+    input_file
 }
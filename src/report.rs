@@ -0,0 +1,247 @@
+//! Summary of all puzzles with their answers, run times and memory usage, as Markdown or HTML.
+use crate::common::*;
+use crate::journal;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write as _;
+use std::fs::write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::time::{Duration, Instant};
+
+/// Wraps the system allocator to keep track of the number of bytes allocated on the heap.
+struct TrackingAllocator;
+
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+impl TrackingAllocator {
+    fn grow(size: usize) {
+        let current = CURRENT_BYTES.fetch_add(size, Relaxed) + size;
+        PEAK_BYTES.fetch_max(current, Relaxed);
+    }
+
+    fn shrink(size: usize) {
+        CURRENT_BYTES.fetch_sub(size, Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::shrink(layout.size());
+            Self::grow(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Debug)]
+pub struct Row {
+    day: usize,
+    answers: Result<Vec<(char, String)>>,
+    time: Duration,
+    peak_memory: usize,
+}
+
+/// Runs `solver` for the given day while measuring its run time and peak heap usage.
+pub fn measure<F>(day: usize, solver: F) -> Row
+where
+    F: FnOnce() -> Result<Vec<(char, String)>>,
+{
+    let baseline = CURRENT_BYTES.load(Relaxed);
+    PEAK_BYTES.store(baseline, Relaxed);
+
+    let start = Instant::now();
+    let answers = solver();
+    let time = start.elapsed();
+
+    Row {
+        day,
+        answers,
+        time,
+        peak_memory: PEAK_BYTES.load(Relaxed).saturating_sub(baseline),
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=9999 => format!("{} B", bytes),
+        10000..=9999999 => format!("{} KiB", bytes / 1024),
+        _ => format!("{} MiB", bytes / (1024 * 1024)),
+    }
+}
+
+fn verify(entries: &[journal::Entry], day: usize, part: char, answer: &str) -> &'static str {
+    match journal::correct_answer(entries, day, part) {
+        Some(correct) if correct == answer => "correct",
+        Some(_) => "WRONG",
+        None if answer.contains('\n') => "rendering",
+        None => "unknown",
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn markdown(rows: &[Row], entries: &[journal::Entry]) -> Result<String> {
+    let mut out = String::new();
+    let mut renderings = vec![];
+
+    writeln!(out, "# Advent of Code 2021\n")?;
+    writeln!(
+        out,
+        "| Day | Part | Answer | Verified | Time | Peak memory |"
+    )?;
+    writeln!(
+        out,
+        "|----:|:----:|-------:|:--------:|-----:|------------:|"
+    )?;
+
+    for row in rows {
+        let time = format!("{:.3} ms", row.time.as_secs_f64() * 1000.0);
+        let memory = format_bytes(row.peak_memory);
+
+        let answers = match &row.answers {
+            Ok(answers) => answers,
+            Err(e) => {
+                writeln!(
+                    out,
+                    "| {} | | error: {} | | {} | {} |",
+                    row.day, e, time, memory
+                )?;
+                continue;
+            }
+        };
+
+        for (part, answer) in answers {
+            let verified = verify(entries, row.day, *part, answer);
+            let text = if answer.contains('\n') {
+                renderings.push((row.day, part, answer));
+                "see below".to_string()
+            } else {
+                format!("`{}`", answer)
+            };
+
+            writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} |",
+                row.day, part, text, verified, time, memory
+            )?;
+        }
+    }
+
+    for (day, part, answer) in renderings {
+        writeln!(
+            out,
+            "\n## Day {} part {}\n\n```text\n{}\n```",
+            day, part, answer
+        )?;
+    }
+
+    Ok(out)
+}
+
+fn html(rows: &[Row], entries: &[journal::Entry]) -> Result<String> {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head><title>Advent of Code 2021</title></head>"
+    )?;
+    writeln!(out, "<body>\n<h1>Advent of Code 2021</h1>\n<table>")?;
+    writeln!(out, "<tr><th>Day</th><th>Part</th><th>Answer</th><th>Verified</th><th>Time</th><th>Peak memory</th></tr>")?;
+
+    for row in rows {
+        let time = format!("{:.3} ms", row.time.as_secs_f64() * 1000.0);
+        let memory = format_bytes(row.peak_memory);
+
+        let answers = match &row.answers {
+            Ok(answers) => answers,
+            Err(e) => {
+                let e = escape_html(&e.to_string());
+                writeln!(out, "<tr><td>{}</td><td></td><td>error: {}</td><td></td><td>{}</td><td>{}</td></tr>", row.day, e, time, memory)?;
+                continue;
+            }
+        };
+
+        for (part, answer) in answers {
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td><pre>{}</pre></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                row.day,
+                part,
+                escape_html(answer),
+                verify(entries, row.day, *part, answer),
+                time,
+                memory
+            )?;
+        }
+    }
+
+    writeln!(out, "</table>\n</body>\n</html>")?;
+    Ok(out)
+}
+
+/// Writes the report to `path`, as HTML if the file name ends in `.html` and as Markdown otherwise.
+pub fn save(path: &Path, rows: &[Row], entries: &[journal::Entry]) -> Result {
+    let content = if path.extension().is_some_and(|ext| ext == "html") {
+        html(rows, entries)?
+    } else {
+        markdown(rows, entries)?
+    };
+
+    write(path, content).with_context(|| format!("failed to write: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let entries = ["1 A 7 correct", "1 B 8 correct"].map(|e| e.parse().unwrap());
+        let rows = [
+            measure(1, || Ok(vec![('A', "7".into()), ('B', "9".into())])),
+            measure(2, || Ok(vec![('A', "x x\n x ".into())])),
+            measure(4, || bail!("invalid input")),
+        ];
+
+        let text = markdown(&rows, &entries).unwrap();
+        assert!(text.contains("| 1 | A | `7` | correct |"));
+        assert!(text.contains("| 1 | B | `9` | WRONG |"));
+        assert!(text.contains("| 2 | A | see below | rendering |"));
+        assert!(text.contains("```text\nx x\n x \n```"));
+        assert!(text.contains("| 4 | | error: invalid input |"));
+
+        let text = html(&rows, &entries).unwrap();
+        assert!(text.contains("<td>1</td><td>B</td><td><pre>9</pre></td><td>WRONG</td>"));
+    }
+}